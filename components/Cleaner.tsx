@@ -63,7 +63,7 @@ const Cleaner: React.FC = () => {
         setCleanResult(result);
       } catch (e) {
         console.error("Clean failed:", e);
-        setCleanResult({ freed_human: '0 B', deleted_count: 0, errors: [String(e)] });
+        setCleanResult({ freed_human: '0 B', deleted_count: 0, errors: [e?.message ?? String(e)] });
       }
     } else {
       await new Promise(r => setTimeout(r, 2000));
@@ -96,6 +96,9 @@ const Cleaner: React.FC = () => {
         </div>
         <h3 className="text-2xl font-bold mb-2">Cleaning Complete!</h3>
         <p className="text-gray-500 mb-2">Freed <span className="font-bold text-emerald-600">{cleanResult.freed_human}</span></p>
+        {cleanResult.trashed_bytes > 0 && (
+          <p className="text-gray-500 mb-2">Moved <span className="font-bold">{cleanResult.trashed_human}</span> to the Trash</p>
+        )}
         <p className="text-gray-400 text-sm mb-6">{cleanResult.deleted_count} items removed</p>
         {cleanResult.errors?.length > 0 && (
           <div className="mb-6 p-3 bg-amber-50 rounded-xl text-xs text-amber-700 max-w-md">
@@ -204,7 +207,7 @@ const Cleaner: React.FC = () => {
       <div className="flex items-start space-x-3 p-4 bg-amber-50 rounded-2xl border border-amber-100">
         <AlertCircle className="text-amber-600 shrink-0" size={20} />
         <p className="text-xs text-amber-800 leading-relaxed">
-          Cleaning system cache is safe. Some apps might take slightly longer to open the first time as they rebuild their cache. Trash items will be permanently deleted.
+          Cleaning system cache is safe. Some apps might take slightly longer to open the first time as they rebuild their cache. Trash items and temporary files will be permanently deleted.
         </p>
       </div>
     </div>
@@ -0,0 +1,313 @@
+//! `surf`: the Surf scanner engine on the command line.
+//!
+//! A front end over `openseal-core`, like the GUI. Prints aligned tables, or
+//! JSON with `--json`, so it can run unattended on build servers and from
+//! cron. Builds without Tauri or any of its system libraries.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::SystemTime;
+
+use clap::{Args, Parser, Subcommand};
+use openseal_core::scanner::{ScanRequest, ScanResult, Scanner, SizeMode};
+use openseal_core::units::human_size;
+use openseal_core::{duplicates, junk};
+use serde::Serialize;
+
+#[derive(Parser)]
+#[command(name = "surf", version, about = "Disk usage analysis from the command line")]
+struct Cli {
+    /// Print JSON instead of tables
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Scan a directory and print a summary
+    Scan(ScanArgs),
+    /// List the largest files
+    Top(ScanArgs),
+    /// Break down usage by file extension
+    Ext(ScanArgs),
+    /// List files not modified for --stale-days days (default 90)
+    Stale(ScanArgs),
+    /// Find files with identical contents
+    Dupes {
+        /// Directory to search
+        path: PathBuf,
+        /// Ignore files smaller than this (e.g. 500K, 10M, 1G)
+        #[arg(long, default_value = "1M", value_parser = parse_size)]
+        min_size: u64,
+    },
+    /// Show how much space each junk category takes
+    Junk,
+}
+
+/// One flag per `ScanRequest` field.
+#[derive(Args)]
+struct ScanArgs {
+    /// Directory to scan
+    path: PathBuf,
+    /// Worker threads (default: one per CPU)
+    #[arg(long)]
+    threads: Option<u16>,
+    /// Ignore files smaller than this (e.g. 500K, 10M, 1G)
+    #[arg(long, value_parser = parse_size)]
+    min_size: Option<u64>,
+    /// Glob pattern of paths to skip; may be repeated
+    #[arg(long, value_name = "PATTERN")]
+    exclude: Vec<String>,
+    /// Days without modification after which a file is stale
+    #[arg(long)]
+    stale_days: Option<u32>,
+    /// Number of files listed by `top` (default 20)
+    #[arg(long)]
+    limit: Option<usize>,
+    /// Count logical sizes (`apparent`) or allocated disk space (`allocated`)
+    #[arg(long, default_value = "apparent", value_parser = parse_size_mode)]
+    size_mode: SizeMode,
+    /// Follow symbolic links instead of counting the links themselves
+    #[arg(long)]
+    follow_symlinks: bool,
+    /// Stay on the filesystem of the scanned directory
+    #[arg(long)]
+    one_file_system: bool,
+    /// Reuse unchanged directories from this cache file, and update it
+    #[arg(long, value_name = "FILE")]
+    cache: Option<PathBuf>,
+}
+
+impl ScanArgs {
+    fn request(self) -> ScanRequest {
+        let mut request = ScanRequest::new(self.path);
+        request.threads = self.threads;
+        request.min_size = self.min_size;
+        request.exclude_patterns = self.exclude;
+        request.stale_days = self.stale_days;
+        request.limit = self.limit;
+        request.size_mode = self.size_mode;
+        request.follow_symlinks = self.follow_symlinks;
+        request.one_file_system = self.one_file_system;
+        request.cache_path = self.cache;
+        request
+    }
+}
+
+#[derive(Serialize)]
+struct JunkSummary {
+    id: &'static str,
+    size_bytes: u64,
+    file_count: usize,
+    dirs: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    // Exit quietly when piped into `head` and the like instead of panicking
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+    }
+    let cli = Cli::parse();
+    match run(cli.command, cli.json) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("surf: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command, json: bool) -> Result<(), String> {
+    match command {
+        Command::Scan(args) => {
+            let result = scan(args.request())?;
+            if json {
+                return print_json(&result.summary);
+            }
+            let s = &result.summary;
+            let rows = [
+                ("Root", s.root_path.display().to_string()),
+                ("Files", s.total_files.to_string()),
+                ("Directories", s.total_dirs.to_string()),
+                ("Size", format!("{} ({} bytes)", human_size(s.total_size_bytes), s.total_size_bytes)),
+                ("Apparent", human_size(s.total_apparent_bytes)),
+                ("Allocated", human_size(s.total_allocated_bytes)),
+                ("Hardlinked", human_size(s.hardlink_shared_bytes)),
+                ("Skipped dirs", result.skipped.len().to_string()),
+                ("Errors", s.error_count.to_string()),
+                ("Unscanned (est.)", human_size(s.unscanned_bytes_estimate)),
+                ("Elapsed", format!("{:.2}s", s.elapsed_seconds)),
+            ];
+            for (label, value) in rows {
+                println!("{:<18}{}", label, value);
+            }
+            Ok(())
+        }
+        Command::Top(args) => {
+            let result = scan(args.request())?;
+            if json {
+                return print_json(&result.top_files);
+            }
+            for file in &result.top_files {
+                println!("{:>10}  {}", human_size(file.size_bytes), file.path.display());
+            }
+            Ok(())
+        }
+        Command::Ext(args) => {
+            let result = scan(args.request())?;
+            if json {
+                return print_json(&result.by_extension);
+            }
+            println!("{:<12}{:>10}{:>12}", "EXTENSION", "FILES", "SIZE");
+            for ext in &result.by_extension {
+                println!("{:<12}{:>10}{:>12}", ext.extension, ext.file_count, human_size(ext.total_size_bytes));
+            }
+            Ok(())
+        }
+        Command::Stale(args) => {
+            let mut request = args.request();
+            request.stale_days.get_or_insert(90);
+            let result = scan(request)?;
+            if json {
+                return print_json(&result.stale_files);
+            }
+            let now = SystemTime::now();
+            for file in &result.stale_files {
+                let days = file
+                    .last_modified
+                    .and_then(|t| now.duration_since(t).ok())
+                    .map(|age| format!("{}d", age.as_secs() / 86_400))
+                    .unwrap_or_else(|| "?".into());
+                println!("{:>10}{:>8}  {}", human_size(file.size_bytes), days, file.path.display());
+            }
+            Ok(())
+        }
+        Command::Dupes { path, min_size } => {
+            if !path.is_dir() {
+                return Err(format!("Not a directory: {}", path.display()));
+            }
+            let sets = duplicates::find_duplicates(&path, min_size);
+            if json {
+                return print_json(&sets);
+            }
+            for set in &sets {
+                println!(
+                    "{} x{} ({} wasted)",
+                    human_size(set.size_bytes),
+                    set.files.len(),
+                    human_size(set.wasted_bytes())
+                );
+                for file in &set.files {
+                    println!("  {}", file.display());
+                }
+            }
+            Ok(())
+        }
+        Command::Junk => {
+            let home = std::env::var("HOME").unwrap_or_default();
+            let mut summaries: Vec<JunkSummary> = junk::junk_dirs(&home)
+                .into_iter()
+                .map(|(id, dirs)| {
+                    let mut files = Vec::new();
+                    let size_bytes = dirs.iter().map(|d| junk::junk_files(d.as_ref(), &mut files)).sum();
+                    JunkSummary { id, size_bytes, file_count: files.len(), dirs }
+                })
+                .collect();
+            summaries.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| a.id.cmp(b.id)));
+            if json {
+                return print_json(&summaries);
+            }
+            println!("{:<16}{:>10}{:>12}", "CATEGORY", "FILES", "SIZE");
+            for s in &summaries {
+                println!("{:<16}{:>10}{:>12}", s.id, s.file_count, human_size(s.size_bytes));
+            }
+            Ok(())
+        }
+    }
+}
+
+fn scan(request: ScanRequest) -> Result<ScanResult, String> {
+    let result = Scanner::new()
+        .scan_sync(&request)
+        .map_err(|e| format!("Scan failed: {}", e))?;
+    if result.summary.error_count > 0 {
+        eprintln!("surf: {} paths could not be read", result.summary.error_count);
+    }
+    Ok(result)
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// Parses a byte count with an optional binary suffix: `1536`, `500K`, `10M`, `1.5G`.
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let upper = s.to_ascii_uppercase();
+    let (number, unit) = upper.split_at(upper.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(upper.len()));
+    let multiplier: u64 = match unit {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return Err(format!("Invalid size unit in {}: expected B, K, M, G or T (optionally followed by B or iB)", s)),
+    };
+    let value: f64 = number.trim().parse().map_err(|_| format!("Invalid size: {}", s))?;
+    if value < 0.0 {
+        return Err(format!("Invalid size: {}", s));
+    }
+    Ok((value * multiplier as f64) as u64)
+}
+
+fn parse_size_mode(s: &str) -> Result<SizeMode, String> {
+    match s {
+        "apparent" => Ok(SizeMode::Apparent),
+        "allocated" => Ok(SizeMode::Allocated),
+        _ => Err(format!("Unknown size mode: {} (expected apparent or allocated)", s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1536"), Ok(1536));
+        assert_eq!(parse_size("500K"), Ok(500 * 1024));
+        assert_eq!(parse_size("10mb"), Ok(10 * 1024 * 1024));
+        assert_eq!(parse_size("1.5GiB"), Ok(3 * 512 * 1024 * 1024));
+        assert!(parse_size("lots").is_err());
+        assert!(parse_size("-1M").is_err());
+        assert_eq!(parse_size("2 KiB"), Ok(2048));
+        assert_eq!(parse_size("7b"), Ok(7));
+        for bad in ["1BB", "1MBB", "1iB", "1Mi", "1X", "1KiBB"] {
+            assert!(parse_size(bad).is_err(), "{} was accepted", bad);
+        }
+    }
+
+    #[test]
+    fn test_scan_args_fill_request() {
+        let cli = Cli::parse_from([
+            "surf", "top", "/data", "--threads", "4", "--min-size", "1M", "--exclude", "*.log",
+            "--exclude", "**/target", "--stale-days", "30", "--limit", "5", "--size-mode", "allocated",
+            "--one-file-system",
+        ]);
+        let Command::Top(args) = cli.command else { panic!("expected top") };
+        let request = args.request();
+        assert_eq!(request.root_path, PathBuf::from("/data"));
+        assert_eq!(request.threads, Some(4));
+        assert_eq!(request.min_size, Some(1024 * 1024));
+        assert_eq!(request.exclude_patterns, vec!["*.log", "**/target"]);
+        assert_eq!(request.stale_days, Some(30));
+        assert_eq!(request.limit, Some(5));
+        assert_eq!(request.size_mode, SizeMode::Allocated);
+        assert!(request.one_file_system && !request.follow_symlinks);
+    }
+}
@@ -0,0 +1,620 @@
+//! Removing junk: whole categories or selected items, to the Trash or
+//! permanently, with a dry run that resolves exactly what a real run would do.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::junk::junk_dirs;
+use crate::scanner::allocated_size;
+use crate::trash;
+use crate::units::human_size;
+
+/// How cleaning commands get rid of files.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteMode {
+    /// Move to the Trash so the deletion can be undone
+    #[default]
+    Trash,
+    /// Delete permanently
+    Permanent,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CleanResult {
+    /// Bytes deleted permanently (in a dry run, that would be)
+    pub freed_bytes: u64,
+    pub freed_human: String,
+    /// Bytes moved to the Trash; they take up space until it is emptied
+    pub trashed_bytes: u64,
+    pub trashed_human: String,
+    /// Top-level entries removed
+    pub deleted_count: u32,
+    /// Files inside them, counted recursively
+    pub deleted_files: u64,
+    pub freed_by_category: HashMap<String, u64>,
+    pub trashed_by_category: HashMap<String, u64>,
+    /// The requested mode; see `category_modes` for what each category used
+    pub mode: DeleteMode,
+    /// Mode each cleaned category was actually removed with
+    pub category_modes: HashMap<String, DeleteMode>,
+    pub dry_run: bool,
+    /// Removed, or that would be removed in a dry run
+    pub paths: Vec<String>,
+    /// Not removable because of permissions
+    pub blocked: Vec<String>,
+    /// Already gone before we got to them
+    pub missing: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl DeleteMode {
+    /// Removes a file, directory tree or symlink (never its target).
+    pub fn remove(self, path: &Path) -> std::io::Result<()> {
+        match self {
+            DeleteMode::Trash => trash::move_to_trash(path).map(|_| ()),
+            DeleteMode::Permanent => {
+                if std::fs::symlink_metadata(path)?.is_dir() {
+                    std::fs::remove_dir_all(path)
+                } else {
+                    std::fs::remove_file(path)
+                }
+            }
+        }
+    }
+}
+
+/// Measures a file or directory tree without following symlinks. Returns
+/// the allocated bytes and the number of non-directory entries.
+fn measure_tree(path: &Path, meta: &std::fs::Metadata) -> (u64, u64) {
+    let mut bytes = allocated_size(meta);
+    if !meta.is_dir() {
+        return (bytes, 1);
+    }
+    let mut files = 0;
+    if let Ok(entries) = std::fs::read_dir(path) {
+        for entry in entries.flatten() {
+            // DirEntry::metadata does not follow symlinks
+            if let Ok(child_meta) = entry.metadata() {
+                let (child_bytes, child_files) = measure_tree(&entry.path(), &child_meta);
+                bytes += child_bytes;
+                files += child_files;
+            }
+        }
+    }
+    (bytes, files)
+}
+
+/// The mode items of a category are removed with. The `trash` category is
+/// always emptied permanently, since moving it to the Trash would free nothing.
+/// So are `temp_files`: `/tmp` is often its own filesystem, where trashing
+/// lands in `/tmp/.Trash-<uid>` and the next scan finds it as junk again.
+fn category_mode(cat_id: &str, mode: DeleteMode) -> DeleteMode {
+    match cat_id {
+        "trash" | "temp_files" => DeleteMode::Permanent,
+        _ => mode,
+    }
+}
+
+/// An entry to remove, with the junk category it belongs to.
+struct CleanTarget {
+    path: PathBuf,
+    category: &'static str,
+    mode: DeleteMode,
+}
+
+/// Top-level entries removed when cleaning `category_ids`.
+fn clean_targets(home: &str, category_ids: &[String], mode: DeleteMode) -> Vec<CleanTarget> {
+    let dir_map = junk_dirs(home);
+    let mut targets = Vec::new();
+    for cat_id in category_ids {
+        if let Some((&category, dirs)) = dir_map.get_key_value(cat_id.as_str()) {
+            for dir in dirs {
+                let path = Path::new(dir);
+                if !path.exists() {
+                    continue;
+                }
+                if let Ok(entries) = std::fs::read_dir(path) {
+                    for entry in entries.flatten() {
+                        targets.push(CleanTarget {
+                            path: entry.path(),
+                            category,
+                            mode: category_mode(category, mode),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    targets
+}
+
+/// Whether the current user may remove an entry with metadata `meta` from
+/// `dir`: needs write and search permission on `dir`, and ownership of the
+/// entry or `dir` when `dir` has the sticky bit set (as `/tmp` does).
+#[cfg(unix)]
+fn can_remove_from(dir: &Path, meta: &std::fs::Metadata) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::MetadataExt;
+
+    let Ok(c_dir) = CString::new(dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    if unsafe { libc::access(c_dir.as_ptr(), libc::W_OK | libc::X_OK) } != 0 {
+        return false;
+    }
+    let Ok(dir_meta) = std::fs::metadata(dir) else {
+        return false;
+    };
+    let euid = unsafe { libc::geteuid() };
+    #[allow(clippy::unnecessary_cast)] // mode_t is u16 on macOS
+    let sticky = dir_meta.mode() & libc::S_ISVTX as u32 != 0;
+    !sticky || euid == 0 || meta.uid() == euid || dir_meta.uid() == euid
+}
+
+#[cfg(not(unix))]
+fn can_remove_from(_dir: &Path, _meta: &std::fs::Metadata) -> bool {
+    true
+}
+
+/// Returns the first path that blocks removing `path` for lack of
+/// permissions, or `None` if it can be removed. With `recursive`, every
+/// entry inside a directory must be removable too, as for a permanent delete;
+/// moving to the Trash only needs the top-level entry.
+fn deletion_blocker(path: &Path, recursive: bool) -> Option<PathBuf> {
+    // Already gone: nothing to block
+    let meta = std::fs::symlink_metadata(path).ok()?;
+    if let Some(parent) = path.parent() {
+        if !can_remove_from(parent, &meta) {
+            return Some(path.to_path_buf());
+        }
+    }
+    if recursive && meta.is_dir() {
+        let entries = match std::fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(_) => return Some(path.to_path_buf()),
+        };
+        for entry in entries.flatten() {
+            if let Some(blocker) = deletion_blocker(&entry.path(), true) {
+                return Some(blocker);
+            }
+        }
+    }
+    None
+}
+
+/// Removes `targets`, or with `dry_run` only reports what would be removed.
+///
+/// Each entry is measured recursively before it is touched, so the freed
+/// bytes reflect the whole tree as allocated on disk.
+fn run_clean(targets: Vec<CleanTarget>, mode: DeleteMode, dry_run: bool) -> CleanResult {
+    let mut freed: u64 = 0;
+    let mut trashed: u64 = 0;
+    let mut deleted: u32 = 0;
+    let mut deleted_files: u64 = 0;
+    let mut freed_by_category: HashMap<String, u64> = HashMap::new();
+    let mut trashed_by_category: HashMap<String, u64> = HashMap::new();
+    let mut category_modes: HashMap<String, DeleteMode> = HashMap::new();
+    let mut paths = Vec::new();
+    let mut blocked = Vec::new();
+    let mut missing = Vec::new();
+    let mut errors = Vec::new();
+
+    for CleanTarget {
+        path: p,
+        category,
+        mode: target_mode,
+    } in targets
+    {
+        let (size, files) = match std::fs::symlink_metadata(&p) {
+            Ok(meta) => measure_tree(&p, &meta),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                missing.push(p.to_string_lossy().to_string());
+                continue;
+            }
+            Err(_) => (0, 0),
+        };
+        let outcome = if dry_run {
+            match deletion_blocker(&p, target_mode == DeleteMode::Permanent) {
+                Some(blocker) => Err(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    format!("permission denied: {}", blocker.display()),
+                )),
+                None => Ok(()),
+            }
+        } else {
+            target_mode.remove(&p)
+        };
+        match outcome {
+            Ok(()) => {
+                // Trashed files keep their blocks until the Trash is emptied
+                let (total, by_category) = match target_mode {
+                    DeleteMode::Trash => (&mut trashed, &mut trashed_by_category),
+                    DeleteMode::Permanent => (&mut freed, &mut freed_by_category),
+                };
+                *total += size;
+                *by_category.entry(category.to_string()).or_default() += size;
+                category_modes.insert(category.to_string(), target_mode);
+                deleted += 1;
+                deleted_files += files;
+                paths.push(p.to_string_lossy().to_string());
+            }
+            Err(e) => {
+                if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    blocked.push(p.to_string_lossy().to_string());
+                }
+                errors.push(format!("{}: {}", p.display(), e));
+            }
+        }
+    }
+
+    CleanResult {
+        freed_bytes: freed,
+        freed_human: human_size(freed),
+        trashed_bytes: trashed,
+        trashed_human: human_size(trashed),
+        deleted_count: deleted,
+        deleted_files,
+        freed_by_category,
+        trashed_by_category,
+        mode,
+        category_modes,
+        dry_run,
+        paths,
+        blocked,
+        missing,
+        errors: if errors.len() > 10 {
+            errors[..10].to_vec()
+        } else {
+            errors
+        },
+    }
+}
+
+/// Empties the given junk categories for the user whose home directory is
+/// `home`.
+///
+/// Items are moved to the Trash unless `mode` is `Permanent`. With `dry_run`
+/// nothing is touched: the result lists exactly what would be removed and
+/// what is blocked by permissions, resolved the same way as a real run.
+pub fn clean_categories(
+    home: &str,
+    category_ids: &[String],
+    mode: DeleteMode,
+    dry_run: bool,
+) -> CleanResult {
+    run_clean(clean_targets(home, category_ids, mode), mode, dry_run)
+}
+
+/// Resolves symlinks in the existing part of `path`, keeping the final
+/// component as is (a symlink is removed, not its target). Components below
+/// the deepest existing ancestor are appended unchanged.
+fn resolve_parent(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?;
+    let mut existing = path.parent()?;
+    let mut rest = Vec::new();
+    let base = loop {
+        match existing.canonicalize() {
+            Ok(base) => break base,
+            Err(_) => {
+                rest.push(existing.file_name()?);
+                existing = existing.parent()?;
+            }
+        }
+    };
+    Some(
+        rest.iter()
+            .rev()
+            .fold(base, |acc, c| acc.join(c))
+            .join(name),
+    )
+}
+
+/// Finds the junk category whose root directory contains `path` (but is not
+/// `path` itself). Relative paths and `..` components are never accepted.
+fn junk_category_of(
+    path: &Path,
+    dir_map: &HashMap<&'static str, Vec<String>>,
+) -> Option<&'static str> {
+    use std::path::Component;
+
+    if !path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+        return None;
+    }
+    let resolved = resolve_parent(path)?;
+    // Roots nest (Yarn's cache lives inside ~/Library/Caches), so the deepest
+    // root wins, with ties broken by category id
+    dir_map
+        .iter()
+        .flat_map(|(cat_id, roots)| {
+            roots
+                .iter()
+                .filter_map(|root| Path::new(root).canonicalize().ok())
+                .map(move |root| (*cat_id, root))
+        })
+        .filter(|(_, root)| resolved != *root && resolved.starts_with(root))
+        .max_by(|(a_id, a_root), (b_id, b_root)| {
+            a_root
+                .components()
+                .count()
+                .cmp(&b_root.components().count())
+                .then(b_id.cmp(a_id))
+        })
+        .map(|(cat_id, _)| cat_id)
+}
+
+/// Removes selected items found by [`crate::junk::scan_junk`] for the user
+/// whose home directory is `home`.
+///
+/// Any path that does not lie inside one of the junk category roots is
+/// refused. Items that were already removed since the scan are listed in
+/// `missing` rather than reported as errors.
+pub fn clean_items(home: &str, paths: Vec<String>, mode: DeleteMode, dry_run: bool) -> CleanResult {
+    clean_items_in(&junk_dirs(home), paths, mode, dry_run)
+}
+
+/// [`clean_items`] against the category roots in `dir_map`.
+fn clean_items_in(
+    dir_map: &HashMap<&'static str, Vec<String>>,
+    paths: Vec<String>,
+    mode: DeleteMode,
+    dry_run: bool,
+) -> CleanResult {
+    let mut targets = Vec::new();
+    let mut rejected = Vec::new();
+    for path in paths {
+        let p = PathBuf::from(&path);
+        match junk_category_of(&p, dir_map) {
+            Some(category) => targets.push(CleanTarget {
+                path: p,
+                category,
+                mode: category_mode(category, mode),
+            }),
+            None => rejected.push(format!("{}: outside the junk locations, refused", path)),
+        }
+    }
+
+    let mut result = run_clean(targets, mode, dry_run);
+    rejected.append(&mut result.errors);
+    rejected.truncate(10);
+    result.errors = rejected;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_permanent_delete_removes_link_not_target() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("target/nested")).unwrap();
+        File::create(root.join("target/nested/keep.txt")).unwrap();
+        std::os::unix::fs::symlink(root.join("target"), root.join("link")).unwrap();
+
+        DeleteMode::Permanent.remove(&root.join("link")).unwrap();
+        assert!(!root.join("link").exists());
+        assert!(root.join("target/nested/keep.txt").exists());
+
+        DeleteMode::Permanent.remove(&root.join("target")).unwrap();
+        assert!(!root.join("target").exists());
+    }
+
+    #[test]
+    fn test_clean_junk_dry_run_matches_real_run() {
+        let dir = tempdir().unwrap();
+        let home = dir.path().to_string_lossy().to_string();
+        let caches = dir.path().join("Library/Caches");
+        fs::create_dir_all(caches.join("com.example.app")).unwrap();
+        File::create(caches.join("com.example.app/blob"))
+            .unwrap()
+            .write_all(&[0; 64])
+            .unwrap();
+        File::create(caches.join("top.cache"))
+            .unwrap()
+            .write_all(&[0; 10])
+            .unwrap();
+
+        let ids = vec!["system_cache".to_string()];
+        let preview = run_clean(
+            clean_targets(&home, &ids, DeleteMode::Permanent),
+            DeleteMode::Permanent,
+            true,
+        );
+        assert!(preview.dry_run);
+        assert_eq!(preview.deleted_count, 2);
+        assert_eq!(preview.deleted_files, 2);
+        assert!(preview.blocked.is_empty());
+        // Nothing is touched in a dry run
+        assert!(caches.join("com.example.app/blob").exists());
+        assert!(caches.join("top.cache").exists());
+
+        let result = run_clean(
+            clean_targets(&home, &ids, DeleteMode::Permanent),
+            DeleteMode::Permanent,
+            false,
+        );
+        assert!(!result.dry_run);
+        let (mut expected, mut removed) = (preview.paths, result.paths);
+        expected.sort();
+        removed.sort();
+        assert_eq!(expected, removed);
+        assert_eq!(preview.freed_bytes, result.freed_bytes);
+        assert_eq!(fs::read_dir(&caches).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_trash_mode_reports_trashed_not_freed() {
+        let dir = tempdir().unwrap();
+        let home = dir.path().to_string_lossy().to_string();
+        let caches = dir.path().join("Library/Caches");
+        fs::create_dir_all(&caches).unwrap();
+        File::create(caches.join("blob"))
+            .unwrap()
+            .write_all(&[0; 64])
+            .unwrap();
+        let trash = dir.path().join(".Trash");
+        fs::create_dir_all(&trash).unwrap();
+        File::create(trash.join("old"))
+            .unwrap()
+            .write_all(&[0; 64])
+            .unwrap();
+        let cache_bytes = allocated_size(&fs::metadata(caches.join("blob")).unwrap());
+        let trash_bytes = allocated_size(&fs::metadata(trash.join("old")).unwrap());
+
+        // A dry run, so nothing reaches the real Trash
+        let ids = vec!["system_cache".to_string(), "trash".to_string()];
+        let result = run_clean(
+            clean_targets(&home, &ids, DeleteMode::Trash),
+            DeleteMode::Trash,
+            true,
+        );
+        assert_eq!(result.deleted_count, 2);
+        assert_eq!(result.mode, DeleteMode::Trash);
+        // Moving to the Trash frees nothing; emptying the Trash is always permanent
+        assert_eq!(result.trashed_bytes, cache_bytes);
+        assert_eq!(result.freed_bytes, trash_bytes);
+        assert_eq!(result.trashed_by_category["system_cache"], cache_bytes);
+        assert_eq!(result.freed_by_category["trash"], trash_bytes);
+        assert_eq!(result.category_modes["system_cache"], DeleteMode::Trash);
+        assert_eq!(result.category_modes["trash"], DeleteMode::Permanent);
+    }
+
+    #[test]
+    fn test_clean_items_stays_inside_junk_roots() {
+        let dir = tempdir().unwrap();
+        let logs = dir.path().join("Library/Logs");
+        // Only the logs root: the temp dir may itself sit under a real junk root such as /tmp
+        let dir_map = HashMap::from([("app_logs", vec![logs.to_string_lossy().to_string()])]);
+        fs::create_dir_all(logs.join("app")).unwrap();
+        File::create(logs.join("app/old.log"))
+            .unwrap()
+            .write_all(&[0; 32])
+            .unwrap();
+        File::create(logs.join("keep.log")).unwrap();
+        let outside = dir.path().join("Documents/thesis.pdf");
+        fs::create_dir_all(outside.parent().unwrap()).unwrap();
+        File::create(&outside).unwrap();
+
+        let paths = vec![
+            logs.join("app/old.log").to_string_lossy().to_string(),
+            logs.join("gone.log").to_string_lossy().to_string(),
+            outside.to_string_lossy().to_string(),
+            logs.join("../../Documents/thesis.pdf")
+                .to_string_lossy()
+                .to_string(),
+            logs.to_string_lossy().to_string(),
+        ];
+        let allocated = allocated_size(&fs::metadata(logs.join("app/old.log")).unwrap());
+        let result = clean_items_in(&dir_map, paths, DeleteMode::Permanent, false);
+
+        assert_eq!(result.deleted_count, 1);
+        assert_eq!(result.freed_bytes, allocated);
+        assert!(!logs.join("app/old.log").exists());
+        assert!(logs.join("keep.log").exists());
+        assert_eq!(
+            result.missing,
+            vec![logs.join("gone.log").to_string_lossy().to_string()]
+        );
+        // The outside file, the `..` escape and the category root itself are refused
+        assert_eq!(result.errors.len(), 3);
+        assert!(result.errors.iter().all(|e| e.contains("refused")));
+        assert!(outside.exists());
+        assert!(logs.exists());
+    }
+
+    #[test]
+    fn test_temp_files_are_never_trashed() {
+        let dir = tempdir().unwrap();
+        let tmp = dir.path().join("tmp");
+        fs::create_dir_all(&tmp).unwrap();
+        File::create(tmp.join("build.log"))
+            .unwrap()
+            .write_all(&[0; 64])
+            .unwrap();
+        let dir_map = HashMap::from([("temp_files", vec![tmp.to_string_lossy().to_string()])]);
+
+        let paths = vec![tmp.join("build.log").to_string_lossy().to_string()];
+        let result = clean_items_in(&dir_map, paths, DeleteMode::Trash, false);
+        assert_eq!(result.deleted_count, 1);
+        assert_eq!(result.trashed_bytes, 0);
+        assert_eq!(result.category_modes["temp_files"], DeleteMode::Permanent);
+        // Deleted outright, not moved into a Trash on the same filesystem
+        assert_eq!(fs::read_dir(&tmp).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_junk_category_prefers_deepest_root() {
+        let dir = tempdir().unwrap();
+        let home = dir.path().to_string_lossy().to_string();
+        let caches = dir.path().join("Library/Caches");
+        fs::create_dir_all(caches.join("Yarn/v6")).unwrap();
+        let dir_map = junk_dirs(&home);
+
+        assert_eq!(
+            junk_category_of(&caches.join("Yarn/v6"), &dir_map),
+            Some("npm_cache")
+        );
+        assert_eq!(
+            junk_category_of(&caches.join("com.example.app"), &dir_map),
+            Some("system_cache")
+        );
+    }
+
+    #[test]
+    fn test_clean_measures_directory_trees() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = tempdir().unwrap();
+        let home = dir.path().to_string_lossy().to_string();
+        let derived = dir
+            .path()
+            .join("Library/Developer/Xcode/DerivedData/App-abc");
+        fs::create_dir_all(derived.join("Build/Products")).unwrap();
+        for name in ["a.o", "b.o", "Build/Products/App"] {
+            File::create(derived.join(name))
+                .unwrap()
+                .write_all(&[1; 20_000])
+                .unwrap();
+        }
+        let logs = dir.path().join("Library/Logs");
+        fs::create_dir_all(&logs).unwrap();
+        File::create(logs.join("x.log"))
+            .unwrap()
+            .write_all(&[1; 100])
+            .unwrap();
+
+        // Expected size: every file and directory in the tree, by allocated blocks
+        let mut expected_derived = 0;
+        let mut stack = vec![derived.clone()];
+        while let Some(p) = stack.pop() {
+            let meta = fs::symlink_metadata(&p).unwrap();
+            expected_derived += meta.blocks() * 512;
+            if meta.is_dir() {
+                stack.extend(fs::read_dir(&p).unwrap().map(|e| e.unwrap().path()));
+            }
+        }
+        let expected_logs = fs::metadata(logs.join("x.log")).unwrap().blocks() * 512;
+
+        let ids = vec!["xcode_derived".to_string(), "app_logs".to_string()];
+        let result = run_clean(
+            clean_targets(&home, &ids, DeleteMode::Permanent),
+            DeleteMode::Permanent,
+            false,
+        );
+        assert_eq!(result.deleted_count, 2);
+        assert_eq!(result.deleted_files, 4);
+        assert!(result.freed_bytes >= 60_000);
+        assert_eq!(result.freed_by_category["xcode_derived"], expected_derived);
+        assert_eq!(result.freed_by_category["app_logs"], expected_logs);
+        assert_eq!(result.freed_bytes, expected_derived + expected_logs);
+        assert!(!derived.exists());
+    }
+}
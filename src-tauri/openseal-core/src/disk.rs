@@ -0,0 +1,321 @@
+//! Capacity and usage of mounted filesystems, read natively with `statvfs`
+//! (and `getfsstat` for the mount list on macOS).
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::error::{OpenSealError, Result};
+
+#[derive(Debug, Serialize)]
+pub struct DiskUsage {
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    /// Bytes available to unprivileged users (excludes the root reserve)
+    pub free_bytes: u64,
+    pub total_gb: f64,
+    pub used_gb: f64,
+    pub free_gb: f64,
+    pub usage_percent: f64,
+}
+
+/// A mounted filesystem.
+#[derive(Debug, Clone, Serialize)]
+pub struct Volume {
+    pub mount_point: PathBuf,
+    /// Device or source the filesystem was mounted from, e.g. `/dev/disk3s1`
+    pub device: String,
+    pub fs_type: String,
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    /// Free blocks, including those reserved for root
+    pub free_bytes: u64,
+    /// Free blocks available to unprivileged users
+    pub available_bytes: u64,
+    pub total_inodes: u64,
+    pub used_inodes: u64,
+    pub free_inodes: u64,
+    pub removable: bool,
+    pub network: bool,
+}
+
+/// Block and inode counts of one filesystem, in bytes and inodes.
+struct FsStats {
+    total_bytes: u64,
+    free_bytes: u64,
+    available_bytes: u64,
+    total_inodes: u64,
+    free_inodes: u64,
+}
+
+/// Usage of the filesystem that `path` lives on.
+pub fn disk_usage(path: &Path) -> Result<DiskUsage> {
+    const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+    let stats = statvfs(path)?;
+    let used_bytes = stats.total_bytes.saturating_sub(stats.free_bytes);
+    Ok(DiskUsage {
+        total_bytes: stats.total_bytes,
+        used_bytes,
+        free_bytes: stats.available_bytes,
+        total_gb: stats.total_bytes as f64 / GB,
+        used_gb: used_bytes as f64 / GB,
+        free_gb: stats.available_bytes as f64 / GB,
+        usage_percent: if stats.total_bytes > 0 { used_bytes as f64 / stats.total_bytes as f64 * 100.0 } else { 0.0 },
+    })
+}
+
+#[cfg(unix)]
+#[allow(clippy::useless_conversion)] // the counts are u32 on macOS
+fn statvfs(path: &Path) -> Result<FsStats> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| OpenSealError::io(path, io::Error::new(io::ErrorKind::InvalidInput, e)))?;
+    let mut st: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut st) } != 0 {
+        return Err(OpenSealError::io(path, io::Error::last_os_error()));
+    }
+    let block = u64::from(st.f_frsize);
+    Ok(FsStats {
+        total_bytes: u64::from(st.f_blocks) * block,
+        free_bytes: u64::from(st.f_bfree) * block,
+        available_bytes: u64::from(st.f_bavail) * block,
+        total_inodes: u64::from(st.f_files),
+        free_inodes: u64::from(st.f_ffree),
+    })
+}
+
+#[cfg(not(unix))]
+fn statvfs(path: &Path) -> Result<FsStats> {
+    Err(OpenSealError::io(path, io::Error::from(io::ErrorKind::Unsupported)))
+}
+
+fn volume(mount_point: PathBuf, device: String, fs_type: String, stats: FsStats, removable: bool) -> Volume {
+    let network = is_network_fs(&fs_type, &device);
+    Volume {
+        mount_point,
+        device,
+        fs_type,
+        total_bytes: stats.total_bytes,
+        used_bytes: stats.total_bytes.saturating_sub(stats.free_bytes),
+        free_bytes: stats.free_bytes,
+        available_bytes: stats.available_bytes,
+        total_inodes: stats.total_inodes,
+        used_inodes: stats.total_inodes.saturating_sub(stats.free_inodes),
+        free_inodes: stats.free_inodes,
+        removable,
+        network,
+    }
+}
+
+/// Whether a filesystem is served over the network, judged by its type or
+/// by a `host:/path` or `//host/share` source.
+fn is_network_fs(fs_type: &str, device: &str) -> bool {
+    const NETWORK_TYPES: &[&str] = &[
+        "nfs", "nfs4", "cifs", "smb", "smb3", "smbfs", "afpfs", "webdav", "davfs", "ncpfs",
+        "9p", "ceph", "glusterfs", "lustre", "fuse.sshfs", "fuse.rclone", "fuse.s3fs",
+    ];
+    NETWORK_TYPES.contains(&fs_type)
+        || device.starts_with("//")
+        || (!device.starts_with('/') && device.contains(":/"))
+}
+
+/// Longest wait for `statvfs` on a network mount whose server may be gone.
+#[cfg(target_os = "linux")]
+const NETWORK_STAT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// `statvfs` on a network mount, given up after `NETWORK_STAT_TIMEOUT`. A
+/// hard-mounted share with an unreachable server blocks the call
+/// indefinitely, so it runs on its own thread, which is left behind on timeout.
+#[cfg(target_os = "linux")]
+fn statvfs_with_timeout(path: &Path) -> Result<FsStats> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let owned = path.to_path_buf();
+    std::thread::spawn(move || {
+        let _ = tx.send(statvfs(&owned));
+    });
+    rx.recv_timeout(NETWORK_STAT_TIMEOUT)
+        .unwrap_or_else(|_| Err(OpenSealError::io(path, io::Error::from(io::ErrorKind::TimedOut))))
+}
+
+/// Lists every mounted filesystem with a nonzero size, in mount order.
+/// Pseudo filesystems such as `proc` and `sysfs` report no blocks and are
+/// left out, as are mounts that cannot be queried, including network mounts
+/// that do not answer within `NETWORK_STAT_TIMEOUT`.
+#[cfg(target_os = "linux")]
+pub fn list_volumes() -> Result<Vec<Volume>> {
+    let mounts = std::fs::read("/proc/self/mounts").map_err(|e| OpenSealError::io("/proc/self/mounts", e))?;
+    let mut volumes: Vec<Volume> = Vec::new();
+    for (device, mount_point, fs_type) in parse_mounts(&mounts) {
+        let stats = if is_network_fs(&fs_type, &device) {
+            statvfs_with_timeout(&mount_point)
+        } else {
+            statvfs(&mount_point)
+        };
+        let Ok(stats) = stats else { continue };
+        if stats.total_bytes == 0 {
+            continue;
+        }
+        // A later mount on the same point hides the earlier one
+        volumes.retain(|v| v.mount_point != mount_point);
+        let removable = is_removable(&device);
+        volumes.push(volume(mount_point, device, fs_type, stats, removable));
+    }
+    Ok(volumes)
+}
+
+/// Parses `/proc/self/mounts` into (device, mount point, fs type). Mount
+/// points keep their raw bytes, which need not be UTF-8.
+#[cfg(target_os = "linux")]
+fn parse_mounts(mounts: &[u8]) -> Vec<(String, PathBuf, String)> {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    mounts
+        .split(|&b| b == b'\n')
+        .filter_map(|line| {
+            let mut fields = line.split(|&b| b == b' ');
+            let device = String::from_utf8_lossy(&unescape_mount_field(fields.next()?)).into_owned();
+            let mount_point = PathBuf::from(OsStr::from_bytes(&unescape_mount_field(fields.next()?)));
+            let fs_type = String::from_utf8_lossy(fields.next()?).into_owned();
+            Some((device, mount_point, fs_type))
+        })
+        .collect()
+}
+
+/// Undoes the octal escapes (`\040` for a space, ...) the kernel applies to
+/// whitespace and backslashes in mount table fields.
+#[cfg(target_os = "linux")]
+fn unescape_mount_field(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes.get(i + 1..i + 4).filter(|_| bytes[i] == b'\\');
+        match escape.and_then(|d| u8::from_str_radix(std::str::from_utf8(d).ok()?, 8).ok()) {
+            Some(byte) => {
+                out.push(byte);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Whether a block device sits on removable media or a USB bus, from sysfs.
+/// A partition's flag lives on the disk that holds it.
+#[cfg(target_os = "linux")]
+fn is_removable(device: &str) -> bool {
+    if !device.starts_with("/dev/") {
+        return false;
+    }
+    let Some(name) = Path::new(device).canonicalize().ok().and_then(|d| d.file_name().map(|n| n.to_owned())) else {
+        return false;
+    };
+    let Ok(sys) = Path::new("/sys/class/block").join(name).canonicalize() else {
+        return false;
+    };
+    let flagged = |dir: &Path| std::fs::read_to_string(dir.join("removable")).is_ok_and(|s| s.trim() == "1");
+    flagged(&sys) || sys.parent().is_some_and(flagged) || sys.to_string_lossy().contains("/usb")
+}
+
+/// Lists every mounted filesystem with a nonzero size, in mount order.
+#[cfg(target_os = "macos")]
+pub fn list_volumes() -> Result<Vec<Volume>> {
+    use std::ffi::{CStr, OsStr};
+    use std::os::unix::ffi::OsStrExt;
+
+    // <sys/mount.h>; not exported by the libc crate
+    const MNT_REMOVABLE: u32 = 0x0000_0200;
+
+    let os_error = || OpenSealError::io("/", io::Error::last_os_error());
+    // getfsstat fills a caller-owned buffer, unlike getmntinfo's shared one
+    let count = unsafe { libc::getfsstat(std::ptr::null_mut(), 0, libc::MNT_NOWAIT) };
+    if count < 0 {
+        return Err(os_error());
+    }
+    // Room for a few mounts that appear in between the two calls
+    let mut mounts: Vec<libc::statfs> = vec![unsafe { std::mem::zeroed() }; count as usize + 8];
+    let size = (mounts.len() * std::mem::size_of::<libc::statfs>()) as libc::c_int;
+    let count = unsafe { libc::getfsstat(mounts.as_mut_ptr(), size, libc::MNT_NOWAIT) };
+    if count < 0 {
+        return Err(os_error());
+    }
+    mounts.truncate(count as usize);
+
+    fn bytes(chars: &[libc::c_char]) -> &[u8] {
+        unsafe { CStr::from_ptr(chars.as_ptr()) }.to_bytes()
+    }
+    let text = |chars: &[libc::c_char]| String::from_utf8_lossy(bytes(chars)).into_owned();
+    Ok(mounts
+        .iter()
+        .filter(|m| m.f_blocks > 0)
+        .map(|m| {
+            let block = u64::from(m.f_bsize);
+            let stats = FsStats {
+                total_bytes: m.f_blocks * block,
+                free_bytes: m.f_bfree * block,
+                available_bytes: m.f_bavail * block,
+                total_inodes: m.f_files,
+                free_inodes: m.f_ffree,
+            };
+            let mut vol = volume(
+                PathBuf::from(OsStr::from_bytes(bytes(&m.f_mntonname))),
+                text(&m.f_mntfromname),
+                text(&m.f_fstypename),
+                stats,
+                m.f_flags & MNT_REMOVABLE != 0,
+            );
+            vol.network |= m.f_flags & libc::MNT_LOCAL as u32 == 0;
+            vol
+        })
+        .collect())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn list_volumes() -> Result<Vec<Volume>> {
+    Err(OpenSealError::io("/", io::Error::from(io::ErrorKind::Unsupported)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_usage_and_volumes() {
+        let usage = disk_usage(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+        assert!(usage.total_bytes > 0);
+        assert!(usage.used_bytes + usage.free_bytes <= usage.total_bytes);
+        assert!(matches!(disk_usage(Path::new("/no/such/dir")), Err(OpenSealError::NotFound(_))));
+
+        // Some listed volume holds this crate
+        let here = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let volumes = list_volumes().unwrap();
+        assert!(volumes.iter().any(|v| here.starts_with(&v.mount_point) && v.total_bytes > 0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_mounts() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mounts = b"/dev/sda1 / ext4 rw,relatime 0 0\n\
+                       //nas/media /mnt/My\\040Media cifs rw 0 0\n\
+                       proc /proc proc rw,nosuid 0 0\n\
+                       /dev/sdb1 /media/caf\xe9 vfat rw 0 0\n";
+        let parsed = parse_mounts(mounts);
+        assert_eq!(parsed.len(), 4);
+        assert_eq!(parsed[1].1, PathBuf::from("/mnt/My Media"));
+        // Not valid UTF-8, but still the path that was mounted
+        assert_eq!(parsed[3].1.as_os_str().as_bytes(), b"/media/caf\xe9");
+        assert!(is_network_fs(&parsed[1].2, &parsed[1].0));
+        assert!(is_network_fs("nfs4", "server:/export"));
+        assert!(!is_network_fs(&parsed[0].2, &parsed[0].0));
+    }
+}
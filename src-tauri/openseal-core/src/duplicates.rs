@@ -0,0 +1,505 @@
+//! Duplicate file detection.
+//!
+//! Files are grouped by size, then by a hash of their first and last blocks,
+//! and only the survivors are confirmed with a hash of their full contents.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::clean::{CleanResult, DeleteMode};
+use crate::error::{OpenSealError, Result};
+use crate::units::human_size;
+
+/// Size of the head and tail blocks hashed in the partial-hash stage.
+const PARTIAL_HASH_BLOCK: u64 = 4096;
+
+/// Numbers the temporary names used by [`replace_with_copy_of`].
+static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+
+/// Files with identical contents.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateSet {
+    /// blake3 digest of the contents, hex-encoded
+    pub hash: String,
+    pub size_bytes: u64,
+    /// Sorted paths of every copy
+    pub files: Vec<PathBuf>,
+}
+
+impl DuplicateSet {
+    /// Bytes that would be freed by keeping a single copy.
+    pub fn wasted_bytes(&self) -> u64 {
+        self.size_bytes * (self.files.len() as u64 - 1)
+    }
+}
+
+/// How [`resolve`] gets rid of the extra copies in a set.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateStrategy {
+    /// Move the extra copies to the Trash
+    Trash,
+    /// Delete the extra copies permanently
+    Delete,
+    /// Replace each extra copy with a hardlink to the kept file
+    Hardlink,
+    /// Replace each extra copy with a copy-on-write clone of the kept file
+    Reflink,
+}
+
+/// Finds duplicate files of at least `min_bytes` under `root`, most wasted
+/// space first. Hidden directories, `node_modules` and `target` are skipped.
+pub fn find_duplicates(root: &Path, min_bytes: u64) -> Vec<DuplicateSet> {
+    // Phase 1: Group files by size
+    let mut size_map: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    // Hardlinks to one inode share their blocks: only the first path found counts
+    let mut seen_inodes: HashSet<(u64, u64)> = HashSet::new();
+    
+    fn walk_dir(dir: &Path, size_map: &mut HashMap<u64, Vec<PathBuf>>, seen_inodes: &mut HashSet<(u64, u64)>, min_bytes: u64) {
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let p = entry.path();
+                if p.is_symlink() { continue; }
+                if p.is_dir() {
+                    // Skip hidden dirs and system dirs
+                    let name = p.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                    if name.starts_with('.') || name == "node_modules" || name == "target" || name == ".git" {
+                        continue;
+                    }
+                    walk_dir(&p, size_map, seen_inodes, min_bytes);
+                } else if let Ok(meta) = p.metadata() {
+                    if meta.len() >= min_bytes && file_id(&meta).is_none_or(|id| seen_inodes.insert(id)) {
+                        size_map.entry(meta.len()).or_default().push(p);
+                    }
+                }
+            }
+        }
+    }
+    
+    walk_dir(root, &mut size_map, &mut seen_inodes, min_bytes);
+    
+    // Phase 2: Within each size bucket, split by a hash of the first and last blocks
+    let candidates: Vec<(u64, PathBuf)> = size_map
+        .into_iter()
+        .filter(|(size, files)| *size > 0 && files.len() >= 2)
+        .flat_map(|(size, files)| files.into_iter().map(move |f| (size, f)))
+        .collect();
+    let candidates: Vec<(u64, PathBuf)> = group_by_hash(candidates, partial_hash)
+        .into_iter()
+        .map(|(size, _, path)| (size, path))
+        .collect();
+    
+    // Phase 3: Confirm the survivors with a hash of the full contents
+    let confirmed = group_by_hash(candidates, |path, _| full_hash(path));
+    
+    let mut by_digest: HashMap<(u64, blake3::Hash), Vec<PathBuf>> = HashMap::new();
+    for (size, digest, file) in confirmed {
+        by_digest.entry((size, digest)).or_default().push(file);
+    }
+    let mut sets: Vec<DuplicateSet> = by_digest
+        .into_iter()
+        .map(|((size, digest), mut files)| {
+            files.sort();
+            DuplicateSet {
+                hash: digest.to_hex().to_string(),
+                size_bytes: size,
+                files,
+            }
+        })
+        .collect();
+    
+    sets.sort_by_key(|set| std::cmp::Reverse(set.wasted_bytes()));
+    sets
+}
+
+/// Hashes the first and last `PARTIAL_HASH_BLOCK` bytes of a file.
+fn partial_hash(path: &Path, size: u64) -> std::io::Result<blake3::Hash> {
+    use std::io::{Read, Seek, SeekFrom};
+    
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buf = vec![0u8; PARTIAL_HASH_BLOCK.min(size) as usize];
+    file.read_exact(&mut buf)?;
+    hasher.update(&buf);
+    if size > PARTIAL_HASH_BLOCK {
+        let tail = PARTIAL_HASH_BLOCK.min(size - PARTIAL_HASH_BLOCK);
+        file.seek(SeekFrom::Start(size - tail))?;
+        buf.truncate(tail as usize);
+        file.read_exact(&mut buf)?;
+        hasher.update(&buf);
+    }
+    Ok(hasher.finalize())
+}
+
+/// Hashes the full contents of a file.
+pub fn full_hash(path: &Path) -> std::io::Result<blake3::Hash> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize())
+}
+
+/// Hashes every candidate in parallel and keeps only those that share both
+/// size and digest with at least one other file. Unreadable files are dropped.
+fn group_by_hash<F>(candidates: Vec<(u64, PathBuf)>, hash: F) -> Vec<(u64, blake3::Hash, PathBuf)>
+where
+    F: Fn(&Path, u64) -> std::io::Result<blake3::Hash> + Sync,
+{
+    use rayon::prelude::*;
+    
+    let hashed: Vec<(u64, blake3::Hash, PathBuf)> = candidates
+        .into_par_iter()
+        .filter_map(|(size, path)| hash(&path, size).ok().map(|digest| (size, digest, path)))
+        .collect();
+    
+    let mut counts: HashMap<(u64, blake3::Hash), usize> = HashMap::new();
+    for (size, digest, _) in &hashed {
+        *counts.entry((*size, *digest)).or_default() += 1;
+    }
+    hashed
+        .into_iter()
+        .filter(|(size, digest, _)| counts[&(*size, *digest)] >= 2)
+        .collect()
+}
+
+/// Removes every copy in `set` except `keep`, using `strategy`.
+///
+/// Each file is re-hashed first; files whose contents changed since the scan
+/// are skipped and reported in `errors`. Paths are compared by where they
+/// lead, so another name for the kept file (through a symlinked directory, or
+/// a hardlink) is never removed.
+pub fn resolve(set: &DuplicateSet, keep: &Path, strategy: DuplicateStrategy) -> Result<CleanResult> {
+    // Resolved before anything is removed; later aliases of a member are dropped
+    let mut seen = HashSet::new();
+    let members: Vec<(&PathBuf, Option<PathBuf>)> = set
+        .files
+        .iter()
+        .map(|f| (f, f.canonicalize().ok()))
+        .filter(|(_, real)| real.as_ref().is_none_or(|r| seen.insert(r.clone())))
+        .collect();
+    let keep_real = keep.canonicalize().ok().filter(|real| seen.contains(real));
+    let Some(keep_real) = keep_real else {
+        return Err(OpenSealError::InvalidInput(format!("{} is not part of this duplicate group", keep.display())));
+    };
+    if !matches_set(keep, set) {
+        return Err(OpenSealError::InvalidInput(format!("{} no longer matches the scanned contents", keep.display())));
+    }
+    
+    let keep_id = std::fs::metadata(&keep_real).ok().as_ref().and_then(file_id);
+    let mut freed: u64 = 0;
+    let mut deleted: u32 = 0;
+    let mut paths = Vec::new();
+    let mut blocked = Vec::new();
+    let mut errors = Vec::new();
+    
+    for (path, real) in members {
+        if real.as_ref() == Some(&keep_real) {
+            continue;
+        }
+        let file = path.to_string_lossy().to_string();
+        if real.is_none() || !matches_set(path, set) {
+            errors.push(format!("{}: contents changed since the scan, skipped", file));
+            continue;
+        }
+        // A hardlink to the kept file shares its data: removing it frees nothing
+        if keep_id.is_some() && std::fs::metadata(path).ok().as_ref().and_then(file_id) == keep_id {
+            if strategy != DuplicateStrategy::Hardlink {
+                errors.push(format!("{}: same file as the one kept, skipped", file));
+            }
+            continue;
+        }
+        let outcome = match strategy {
+            DuplicateStrategy::Trash => DeleteMode::Trash.remove(path),
+            DuplicateStrategy::Delete => DeleteMode::Permanent.remove(path),
+            DuplicateStrategy::Hardlink | DuplicateStrategy::Reflink => replace_with_copy_of(path, keep, strategy),
+        };
+        match outcome {
+            Ok(()) => {
+                freed += set.size_bytes;
+                deleted += 1;
+                paths.push(file);
+            }
+            Err(e) => {
+                if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    blocked.push(file.clone());
+                }
+                errors.push(format!("{}: {}", file, e));
+            }
+        }
+    }
+    
+    // Trashed copies keep their blocks until the Trash is emptied
+    let mode = if strategy == DuplicateStrategy::Trash { DeleteMode::Trash } else { DeleteMode::Permanent };
+    let trashed = if mode == DeleteMode::Trash { std::mem::take(&mut freed) } else { 0 };
+    Ok(CleanResult {
+        freed_bytes: freed,
+        freed_human: human_size(freed),
+        trashed_bytes: trashed,
+        trashed_human: human_size(trashed),
+        deleted_count: deleted,
+        deleted_files: deleted as u64,
+        freed_by_category: HashMap::new(),
+        trashed_by_category: HashMap::new(),
+        mode,
+        category_modes: HashMap::new(),
+        dry_run: false,
+        paths,
+        blocked,
+        missing: Vec::new(),
+        errors: if errors.len() > 10 { errors[..10].to_vec() } else { errors },
+    })
+}
+
+/// Re-hashes `path` and checks it still has the size and digest of `set`.
+fn matches_set(path: &Path, set: &DuplicateSet) -> bool {
+    match std::fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_file() && meta.len() == set.size_bytes => {
+            full_hash(path).is_ok_and(|digest| digest.to_hex().as_str() == set.hash)
+        }
+        _ => false,
+    }
+}
+
+/// Device and inode number identifying a file's contents on disk.
+#[cfg(unix)]
+fn file_id(meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Creates a copy-on-write clone of `src` at `dst` (APFS `clonefile`).
+#[cfg(target_os = "macos")]
+fn reflink(src: &Path, dst: &Path) -> std::io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    
+    let to_cstring = |p: &Path| CString::new(p.as_os_str().as_bytes())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e));
+    let (src, dst) = (to_cstring(src)?, to_cstring(dst)?);
+    if unsafe { libc::clonefile(src.as_ptr(), dst.as_ptr(), 0) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Creates a copy-on-write clone of `src` at `dst` (`FICLONE` on Btrfs, XFS, ...).
+#[cfg(target_os = "linux")]
+fn reflink(src: &Path, dst: &Path) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    
+    let src_file = std::fs::File::open(src)?;
+    let dst_file = std::fs::OpenOptions::new().write(true).create_new(true).open(dst)?;
+    if unsafe { libc::ioctl(dst_file.as_raw_fd(), libc::FICLONE, src_file.as_raw_fd()) } != 0 {
+        let err = std::io::Error::last_os_error();
+        drop(dst_file);
+        let _ = std::fs::remove_file(dst);
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn reflink(_src: &Path, _dst: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Copy-on-write clones are not supported on this platform"))
+}
+
+/// Replaces `path` with a hardlink or clone of `keep`.
+///
+/// The link is created next to `path` first and then renamed over it, so
+/// `path` is never missing if anything fails half-way. The temporary name is
+/// unique to this call, so a leftover from a crashed run cannot block it.
+fn replace_with_copy_of(path: &Path, keep: &Path, strategy: DuplicateStrategy) -> std::io::Result<()> {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let link = |tmp: &Path| match strategy {
+        DuplicateStrategy::Hardlink => std::fs::hard_link(keep, tmp),
+        _ => reflink(keep, tmp),
+    };
+    let mut attempts = 0;
+    let tmp = loop {
+        let id = NEXT_TMP.fetch_add(1, Ordering::Relaxed);
+        let tmp = path.with_file_name(tmp_name(&name, id));
+        match link(&tmp) {
+            Ok(()) => break tmp,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && attempts < 16 => attempts += 1,
+            Err(e) => return Err(e),
+        }
+    };
+    if strategy == DuplicateStrategy::Reflink {
+        // A clone starts with default permissions; keep the ones of the file it replaces
+        if let Ok(meta) = std::fs::metadata(path) {
+            let _ = std::fs::set_permissions(&tmp, meta.permissions());
+        }
+    }
+    if let Err(e) = std::fs::rename(&tmp, path) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn tmp_name(name: &str, id: u64) -> String {
+    format!(".{}.{}-{}.openseal-tmp", name, std::process::id(), id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_find_duplicates_confirms_full_content() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+
+        // Same size and same head/tail blocks; c.bin differs by one byte in the middle
+        let mut content = vec![b'a'; 3 * 4096];
+        for name in ["a.bin", "b.bin"] {
+            File::create(root.join(name)).unwrap().write_all(&content).unwrap();
+        }
+        content[4096 + 10] = b'b';
+        File::create(root.join("c.bin")).unwrap().write_all(&content).unwrap();
+        // Same size, different tail block
+        content[3 * 4096 - 1] = b'c';
+        File::create(root.join("d.bin")).unwrap().write_all(&content).unwrap();
+        // Empty files are never reported as duplicates
+        File::create(root.join("empty1")).unwrap();
+        File::create(root.join("empty2")).unwrap();
+
+        let sets = find_duplicates(root, 0);
+        assert_eq!(sets.len(), 1);
+        let set = &sets[0];
+        assert_eq!(set.size_bytes, 3 * 4096);
+        assert_eq!(set.files, vec![root.join("a.bin"), root.join("b.bin")]);
+        assert_eq!(set.hash, full_hash(&root.join("a.bin")).unwrap().to_hex().to_string());
+        assert_eq!(set.wasted_bytes(), 3 * 4096);
+    }
+
+    #[test]
+    fn test_find_duplicates_skips_hardlinks() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        File::create(root.join("a.bin")).unwrap().write_all(&[b'x'; 100]).unwrap();
+        fs::hard_link(root.join("a.bin"), root.join("a-link.bin")).unwrap();
+
+        // Two names for one inode waste nothing
+        assert!(find_duplicates(root, 0).is_empty());
+
+        // A real copy is reported once, alongside one of the linked names
+        File::create(root.join("c.bin")).unwrap().write_all(&[b'x'; 100]).unwrap();
+        let sets = find_duplicates(root, 0);
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].files.len(), 2);
+        assert!(sets[0].files.contains(&root.join("c.bin")));
+        assert_eq!(sets[0].wasted_bytes(), 100);
+    }
+
+    /// Held by tests that link files, so each knows which temp name comes next
+    static LINKING: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    fn duplicate_set(root: &Path) -> DuplicateSet {
+        let sets = find_duplicates(root, 0);
+        assert_eq!(sets.len(), 1);
+        sets.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn test_resolve_hardlink_rechecks_contents() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for name in ["a.bin", "b.bin", "c.bin"] {
+            File::create(root.join(name)).unwrap().write_all(&[b'x'; 100]).unwrap();
+        }
+        let set = duplicate_set(root);
+
+        // A file holding the temp name the next link would use must not get in the way
+        let _linking = LINKING.lock().unwrap();
+        let stale = root.join(tmp_name("b.bin", NEXT_TMP.load(Ordering::Relaxed)));
+        File::create(&stale).unwrap();
+        // c.bin changes after the scan: it must be left alone
+        File::create(root.join("c.bin")).unwrap().write_all(&[b'y'; 100]).unwrap();
+
+        let result = resolve(&set, &root.join("a.bin"), DuplicateStrategy::Hardlink).unwrap();
+        assert_eq!(result.deleted_count, 1);
+        assert_eq!(result.freed_bytes, 100);
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].contains("c.bin"));
+
+        let a = fs::metadata(root.join("a.bin")).unwrap();
+        let b = fs::metadata(root.join("b.bin")).unwrap();
+        assert_eq!(a.ino(), b.ino());
+        assert_eq!(fs::read(root.join("c.bin")).unwrap(), vec![b'y'; 100]);
+        // Only the file that held the first temp name is left over
+        let leftovers: Vec<_> = fs::read_dir(root).unwrap().flatten().map(|e| e.file_name()).collect();
+        assert_eq!(leftovers.len(), 4);
+        assert!(stale.exists());
+    }
+
+    #[test]
+    fn test_resolve_never_removes_the_kept_file() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("real")).unwrap();
+        std::os::unix::fs::symlink(root.join("real"), root.join("alias")).unwrap();
+        for name in ["real/a.bin", "b.bin"] {
+            File::create(root.join(name)).unwrap().write_all(&[b'x'; 100]).unwrap();
+        }
+        fs::hard_link(root.join("real/a.bin"), root.join("a-link.bin")).unwrap();
+        // The set names the kept file three ways besides its own path
+        let set = DuplicateSet {
+            hash: full_hash(&root.join("b.bin")).unwrap().to_hex().to_string(),
+            size_bytes: 100,
+            files: vec![
+                root.join("a-link.bin"),
+                root.join("alias/a.bin"),
+                root.join("b.bin"),
+                root.join("real/a.bin"),
+                root.join("real/../alias/a.bin"),
+            ],
+        };
+
+        let _linking = LINKING.lock().unwrap();
+        for strategy in [DuplicateStrategy::Delete, DuplicateStrategy::Hardlink] {
+            File::create(root.join("b.bin")).unwrap().write_all(&[b'x'; 100]).unwrap();
+            let result = resolve(&set, &root.join("alias/a.bin"), strategy).unwrap();
+            assert_eq!(result.deleted_count, 1, "{:?}", strategy);
+            assert_eq!(fs::read(root.join("real/a.bin")).unwrap(), vec![b'x'; 100]);
+            assert!(root.join("a-link.bin").exists());
+        }
+        assert!(matches!(
+            resolve(&set, &root.join("missing.bin"), DuplicateStrategy::Delete),
+            Err(OpenSealError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_delete() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for name in ["a.bin", "b.bin"] {
+            File::create(root.join(name)).unwrap().write_all(&[b'x'; 100]).unwrap();
+        }
+        let set = duplicate_set(root);
+
+        // The file to keep must belong to the set
+        let outsider = resolve(&set, &root.join("other.bin"), DuplicateStrategy::Delete);
+        assert!(matches!(outsider, Err(OpenSealError::InvalidInput(_))));
+
+        let result = resolve(&set, &root.join("b.bin"), DuplicateStrategy::Delete).unwrap();
+        assert_eq!(result.deleted_count, 1);
+        assert!(result.errors.is_empty());
+        assert!(!root.join("a.bin").exists());
+        assert!(root.join("b.bin").exists());
+    }
+}
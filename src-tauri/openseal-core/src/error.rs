@@ -0,0 +1,156 @@
+//! The error type shared by the core and every front end.
+//!
+//! Each variant has a stable `code` that callers can match on (and localize
+//! from) instead of parsing the English message. It serializes as
+//! `{ "code": ..., "message": ..., "path": ... }`, with `path` set only for
+//! errors about a specific file or directory.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum OpenSealError {
+    /// A path the operation needs does not exist
+    #[error("Path not found: {}", .0.display())]
+    NotFound(PathBuf),
+    /// The current user may not read or change a path
+    #[error("Permission denied: {}", .0.display())]
+    PermissionDenied(PathBuf),
+    /// Any other I/O failure on a path
+    #[error("{}: {message}", path.display())]
+    Io { path: PathBuf, message: String },
+    /// A stored file exists but cannot be used (corrupt, or an unknown format version)
+    #[error("{}: {message}", path.display())]
+    InvalidData { path: PathBuf, message: String },
+    /// The caller passed an argument the operation refuses
+    #[error("{0}")]
+    InvalidInput(String),
+    /// No scan with this id was started
+    #[error("Unknown scan id: {0}")]
+    UnknownScan(u64),
+    /// The scan is still running
+    #[error("Scan {0} has not finished")]
+    ScanNotFinished(u64),
+    /// The scan was canceled, so its results are incomplete
+    #[error("Scan {0} was canceled; its results are incomplete")]
+    ScanCanceled(u64),
+    /// The scan has no running watcher
+    #[error("Scan {0} is not being watched")]
+    NotWatching(u64),
+    /// The per-user data or cache directory cannot be determined
+    #[error("Cannot resolve the app data directory")]
+    AppDirUnavailable,
+    /// The scan thread pool could not be started
+    #[error("Cannot start scan threads: {0}")]
+    ThreadPool(String),
+    /// Work moved off the main thread panicked or was canceled
+    #[error("Background task failed: {0}")]
+    Task(String),
+    /// The filesystem watcher could not be set up
+    #[error("Cannot watch for changes: {0}")]
+    Watch(String),
+    /// No process with this pid exists
+    #[error("No process with pid {0}")]
+    ProcessNotFound(u32),
+    /// The process may not be signaled from the app (init, or the app itself)
+    #[error("Process {0} is protected")]
+    ProtectedProcess(u32),
+    /// The process belongs to another user and cannot be inspected without root
+    #[error("Not allowed to inspect process {0}")]
+    ProcessAccessDenied(u32),
+}
+
+pub type Result<T> = std::result::Result<T, OpenSealError>;
+
+impl OpenSealError {
+    /// Wraps an I/O error on `path`, keeping "not found" and "permission
+    /// denied" distinguishable.
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        let path = path.into();
+        match source.kind() {
+            io::ErrorKind::NotFound => OpenSealError::NotFound(path),
+            io::ErrorKind::PermissionDenied => OpenSealError::PermissionDenied(path),
+            _ => OpenSealError::Io { path, message: source.to_string() },
+        }
+    }
+
+    /// Stable machine-readable identifier of the error kind.
+    pub fn code(&self) -> &'static str {
+        match self {
+            OpenSealError::NotFound(_) => "not_found",
+            OpenSealError::PermissionDenied(_) => "permission_denied",
+            OpenSealError::Io { .. } => "io",
+            OpenSealError::InvalidData { .. } => "invalid_data",
+            OpenSealError::InvalidInput(_) => "invalid_input",
+            OpenSealError::UnknownScan(_) => "unknown_scan",
+            OpenSealError::ScanNotFinished(_) => "scan_not_finished",
+            OpenSealError::ScanCanceled(_) => "scan_canceled",
+            OpenSealError::NotWatching(_) => "not_watching",
+            OpenSealError::AppDirUnavailable => "app_dir_unavailable",
+            OpenSealError::ThreadPool(_) => "thread_pool",
+            OpenSealError::Task(_) => "task_failed",
+            OpenSealError::Watch(_) => "watch_failed",
+            OpenSealError::ProcessNotFound(_) => "process_not_found",
+            OpenSealError::ProtectedProcess(_) => "protected_process",
+            OpenSealError::ProcessAccessDenied(_) => "process_access_denied",
+        }
+    }
+
+    /// The file or directory the error is about, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            OpenSealError::NotFound(path)
+            | OpenSealError::PermissionDenied(path)
+            | OpenSealError::Io { path, .. }
+            | OpenSealError::InvalidData { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for OpenSealError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("OpenSealError", 3)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.serialize_field("path", &self.path())?;
+        s.end()
+    }
+}
+
+impl From<rayon::ThreadPoolBuildError> for OpenSealError {
+    fn from(e: rayon::ThreadPoolBuildError) -> Self {
+        OpenSealError::ThreadPool(e.to_string())
+    }
+}
+
+impl From<notify::Error> for OpenSealError {
+    fn from(e: notify::Error) -> Self {
+        OpenSealError::Watch(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_serializes_code_message_and_path() {
+        let denied = OpenSealError::io("/private/var", io::Error::from(io::ErrorKind::PermissionDenied));
+        assert_eq!(denied, OpenSealError::PermissionDenied(PathBuf::from("/private/var")));
+        assert_eq!(
+            serde_json::to_value(&denied).unwrap(),
+            serde_json::json!({
+                "code": "permission_denied",
+                "message": "Permission denied: /private/var",
+                "path": "/private/var",
+            })
+        );
+
+        let json = serde_json::to_value(OpenSealError::ScanCanceled(3)).unwrap();
+        assert_eq!(json["code"], "scan_canceled");
+        assert!(json["path"].is_null());
+    }
+}
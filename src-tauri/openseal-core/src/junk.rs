@@ -0,0 +1,185 @@
+//! Junk categories: where each one lives and how much it holds.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::units::human_size;
+
+#[derive(Debug, Serialize)]
+pub struct JunkCategory {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub size_bytes: u64,
+    pub size_human: String,
+    pub items: Vec<JunkItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JunkItem {
+    pub path: String,
+    pub size_bytes: u64,
+    pub size_human: String,
+}
+
+/// Root directories of each junk category, keyed by category id.
+pub fn junk_dirs(home: &str) -> HashMap<&'static str, Vec<String>> {
+    HashMap::from([
+        ("system_cache", vec![format!("{}/Library/Caches", home)]),
+        ("app_logs", vec![format!("{}/Library/Logs", home)]),
+        ("trash", vec![format!("{}/.Trash", home)]),
+        ("temp_files", vec!["/tmp".into(), "/var/tmp".into()]),
+        ("xcode_derived", vec![format!("{}/Library/Developer/Xcode/DerivedData", home)]),
+        ("npm_cache", vec![
+            format!("{}/.npm/_cacache", home),
+            format!("{}/Library/Caches/Yarn", home),
+        ]),
+    ])
+}
+
+/// Appends every file below `dir` to `out` with its size and returns their
+/// total size. A missing or unreadable directory contributes nothing.
+pub fn junk_files(dir: &Path, out: &mut Vec<(PathBuf, u64)>) -> u64 {
+    let mut total: u64 = 0;
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let p = entry.path();
+            if let Ok(meta) = p.metadata() {
+                if meta.is_file() {
+                    total += meta.len();
+                    out.push((p, meta.len()));
+                } else if meta.is_dir() {
+                    total += junk_files(&p, out);
+                }
+            }
+        }
+    }
+    total
+}
+
+fn scan_junk_dir(dir: &str, items: &mut Vec<JunkItem>) -> u64 {
+    let mut files = Vec::new();
+    let total = junk_files(Path::new(dir), &mut files);
+    items.extend(files.into_iter().map(|(path, size)| JunkItem {
+        path: path.to_string_lossy().to_string(),
+        size_bytes: size,
+        size_human: human_size(size),
+    }));
+    total
+}
+
+/// Measures every junk category for the user whose home directory is
+/// `home`, largest first. Categories that only exist with some tools
+/// installed are left out when empty.
+pub fn scan_junk(home: &str) -> Vec<JunkCategory> {
+    let mut categories = Vec::new();
+
+    // 1. System caches (user-level)
+    {
+        let mut items = Vec::new();
+        let dir = format!("{}/Library/Caches", home);
+        let size = scan_junk_dir(&dir, &mut items);
+        categories.push(JunkCategory {
+            id: "system_cache".into(),
+            name: "System & App Cache".into(),
+            description: "Temporary cached data from applications and macOS".into(),
+            size_bytes: size,
+            size_human: human_size(size),
+            items,
+        });
+    }
+
+    // 2. Application logs
+    {
+        let mut items = Vec::new();
+        let dir = format!("{}/Library/Logs", home);
+        let size = scan_junk_dir(&dir, &mut items);
+        categories.push(JunkCategory {
+            id: "app_logs".into(),
+            name: "Application Logs".into(),
+            description: "Log files generated by applications".into(),
+            size_bytes: size,
+            size_human: human_size(size),
+            items,
+        });
+    }
+
+    // 3. Trash
+    {
+        let mut items = Vec::new();
+        let dir = format!("{}/.Trash", home);
+        let size = scan_junk_dir(&dir, &mut items);
+        categories.push(JunkCategory {
+            id: "trash".into(),
+            name: "Trash Bin".into(),
+            description: "Files you've deleted but not emptied from Trash".into(),
+            size_bytes: size,
+            size_human: human_size(size),
+            items,
+        });
+    }
+
+    // 4. Temporary files
+    {
+        let mut items = Vec::new();
+        let mut size: u64 = 0;
+        for dir in &["/tmp", "/var/tmp"] {
+            size += scan_junk_dir(dir, &mut items);
+        }
+        categories.push(JunkCategory {
+            id: "temp_files".into(),
+            name: "Temporary Files".into(),
+            description: "System temp files in /tmp and /var/tmp".into(),
+            size_bytes: size,
+            size_human: human_size(size),
+            items,
+        });
+    }
+
+    // 5. Xcode derived data (if exists)
+    {
+        let xcode_dir = format!("{}/Library/Developer/Xcode/DerivedData", home);
+        if Path::new(&xcode_dir).exists() {
+            let mut items = Vec::new();
+            let size = scan_junk_dir(&xcode_dir, &mut items);
+            categories.push(JunkCategory {
+                id: "xcode_derived".into(),
+                name: "Xcode Derived Data".into(),
+                description: "Build artifacts from Xcode projects".into(),
+                size_bytes: size,
+                size_human: human_size(size),
+                items,
+            });
+        }
+    }
+
+    // 6. npm/yarn cache
+    {
+        let mut items = Vec::new();
+        let mut size: u64 = 0;
+        let npm_cache = format!("{}/.npm/_cacache", home);
+        if Path::new(&npm_cache).exists() {
+            size += scan_junk_dir(&npm_cache, &mut items);
+        }
+        let yarn_cache = format!("{}/Library/Caches/Yarn", home);
+        if Path::new(&yarn_cache).exists() {
+            size += scan_junk_dir(&yarn_cache, &mut items);
+        }
+        if size > 0 {
+            categories.push(JunkCategory {
+                id: "npm_cache".into(),
+                name: "npm/Yarn Cache".into(),
+                description: "Cached packages from npm and Yarn".into(),
+                size_bytes: size,
+                size_human: human_size(size),
+                items,
+            });
+        }
+    }
+
+    // Sort by size descending
+    categories.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
+    categories
+}
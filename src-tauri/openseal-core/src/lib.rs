@@ -0,0 +1,22 @@
+//! OpenSeal's core: the Surf scanner and everything built on it — snapshots,
+//! live watching, duplicate detection, junk cleaning — plus process, startup
+//! item and disk usage inspection.
+//!
+//! Nothing here depends on Tauri. The desktop app and the `surf` CLI are thin
+//! front ends that turn these types into commands and output.
+
+pub mod clean;
+pub mod disk;
+pub mod duplicates;
+pub mod error;
+pub mod junk;
+pub mod monitor;
+pub mod process;
+pub mod scanner;
+pub mod snapshot;
+pub mod startup;
+pub mod trash;
+pub mod units;
+pub mod watcher;
+
+pub use error::{OpenSealError, Result};
@@ -0,0 +1,280 @@
+//! Current load per process, measured by sampling in the background.
+//!
+//! `ProcessInfo::cpu_percent` is a lifetime average, so a process that has
+//! just started spinning still looks idle. The monitor reads every process's
+//! CPU time and I/O counters at a fixed interval and reports the rates
+//! between the last two readings. It starts sampling on first use and stops
+//! once nobody has asked for a while. Readings are taken without holding the
+//! monitor's lock, so queries never wait on a pass over every process.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::process::{all_processes, process_io, IoCounters, ProcessInfo};
+
+/// Time between two readings.
+const INTERVAL: Duration = Duration::from_secs(2);
+/// Time between the two readings taken before the first answer after idling.
+const WARMUP: Duration = Duration::from_millis(500);
+/// Sampling stops when no one has queried the monitor for this long.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessSample {
+    /// `cpu_percent` is the share of one core used between the last two readings
+    #[serde(flatten)]
+    pub process: ProcessInfo,
+    pub read_bytes_per_sec: f64,
+    pub write_bytes_per_sec: f64,
+    /// Length of the measured window. 0 when the process has no earlier
+    /// reading yet, in which case `cpu_percent` is still the lifetime average.
+    pub interval_ms: u64,
+}
+
+impl ProcessSample {
+    fn io_bytes_per_sec(&self) -> f64 {
+        self.read_bytes_per_sec + self.write_bytes_per_sec
+    }
+}
+
+/// Order of `ProcessMonitor::top`, busiest first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessSort {
+    #[default]
+    Cpu,
+    /// Resident memory
+    Memory,
+    /// Storage reads plus writes per second
+    Io,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProcessFilter {
+    /// Case-insensitive substring of the name or command line
+    pub query: Option<String>,
+    /// Only processes owned by this user
+    pub user: Option<String>,
+    /// Hide processes using less CPU than this
+    pub min_cpu_percent: f64,
+}
+
+impl ProcessFilter {
+    fn matches(&self, sample: &ProcessSample) -> bool {
+        let process = &sample.process;
+        let query_matches = self.query.as_deref().map(str::to_lowercase).is_none_or(|q| {
+            process.name.to_lowercase().contains(&q) || process.command.to_lowercase().contains(&q)
+        });
+        query_matches
+            && self.user.as_ref().is_none_or(|user| &process.user == user)
+            && process.cpu_percent >= self.min_cpu_percent
+    }
+}
+
+/// What a process had used at the previous reading.
+struct Reading {
+    cpu_time_ms: u64,
+    io: Option<IoCounters>,
+}
+
+/// One pass over every process, with its I/O counters.
+struct Readings {
+    processes: Vec<(ProcessInfo, Option<IoCounters>)>,
+    taken_at: Instant,
+}
+
+impl Readings {
+    fn take() -> Result<Self> {
+        let processes = all_processes()?
+            .into_iter()
+            .map(|process| {
+                let io = process_io(process.pid).ok();
+                (process, io)
+            })
+            .collect();
+        Ok(Readings { processes, taken_at: Instant::now() })
+    }
+}
+
+/// Turns consecutive process listings into per-process rates.
+#[derive(Default)]
+struct Sampler {
+    /// Keyed by pid and start time, so a reused pid starts from scratch
+    previous: HashMap<(u32, u64), Reading>,
+    taken_at: Option<Instant>,
+    samples: Vec<ProcessSample>,
+}
+
+impl Sampler {
+    /// Turns `readings` into rates against the previous readings.
+    fn apply(&mut self, readings: Readings) {
+        let now = readings.taken_at;
+        let window = self.taken_at.map(|t| now - t).filter(|w| !w.is_zero());
+        let mut previous = HashMap::with_capacity(readings.processes.len());
+
+        self.samples = readings
+            .processes
+            .into_iter()
+            .map(|(process, io)| {
+                let key = (process.pid, process.start_time);
+                let reading = Reading { cpu_time_ms: process.cpu_time_ms, io };
+                let mut sample = ProcessSample { process, read_bytes_per_sec: 0.0, write_bytes_per_sec: 0.0, interval_ms: 0 };
+                if let (Some(window), Some(previous)) = (window, self.previous.get(&key)) {
+                    let secs = window.as_secs_f64();
+                    let cpu_ms = reading.cpu_time_ms.saturating_sub(previous.cpu_time_ms);
+                    sample.process.cpu_percent = cpu_ms as f64 / 10.0 / secs;
+                    if let (Some(io), Some(previous_io)) = (reading.io, previous.io) {
+                        sample.read_bytes_per_sec = io.read_bytes.saturating_sub(previous_io.read_bytes) as f64 / secs;
+                        sample.write_bytes_per_sec = io.write_bytes.saturating_sub(previous_io.write_bytes) as f64 / secs;
+                    }
+                    sample.interval_ms = window.as_millis() as u64;
+                }
+                previous.insert(key, reading);
+                sample
+            })
+            .collect();
+        self.previous = previous;
+        self.taken_at = Some(now);
+    }
+
+    fn top(&self, sort: ProcessSort, filter: &ProcessFilter, limit: usize) -> Vec<ProcessSample> {
+        let mut samples: Vec<ProcessSample> = self.samples.iter().filter(|s| filter.matches(s)).cloned().collect();
+        samples.sort_by(|a, b| {
+            let order = match sort {
+                ProcessSort::Cpu => b.process.cpu_percent.total_cmp(&a.process.cpu_percent),
+                ProcessSort::Memory => b.process.rss_bytes.cmp(&a.process.rss_bytes),
+                ProcessSort::Io => b.io_bytes_per_sec().total_cmp(&a.io_bytes_per_sec()),
+            };
+            order.then(a.process.pid.cmp(&b.process.pid))
+        });
+        samples.truncate(limit);
+        samples
+    }
+}
+
+#[derive(Default)]
+struct MonitorState {
+    sampler: Sampler,
+    last_query: Option<Instant>,
+    running: bool,
+}
+
+/// Samples every process on a background thread while it is being queried.
+#[derive(Default)]
+pub struct ProcessMonitor {
+    state: Arc<Mutex<MonitorState>>,
+    /// Held while sampling starts, so concurrent first queries share one warm-up
+    starting: Mutex<()>,
+}
+
+impl ProcessMonitor {
+    /// The `limit` processes that match `filter`, ordered by `sort`, as of the
+    /// latest reading.
+    pub fn top(&self, sort: ProcessSort, filter: &ProcessFilter, limit: usize) -> Result<Vec<ProcessSample>> {
+        self.with_sampler(|sampler| sampler.top(sort, filter, limit))
+    }
+
+    /// Every process as of the latest reading, with `cpu_percent` measured
+    /// over the last interval.
+    pub fn processes(&self) -> Result<Vec<ProcessInfo>> {
+        self.with_sampler(|sampler| sampler.samples.iter().map(|s| s.process.clone()).collect())
+    }
+
+    /// Runs `f` on the sampler, starting the sampling thread if it is not running.
+    fn with_sampler<T>(&self, f: impl FnOnce(&Sampler) -> T) -> Result<T> {
+        self.start()?;
+        let mut state = self.state.lock().unwrap();
+        state.last_query = Some(Instant::now());
+        Ok(f(&state.sampler))
+    }
+
+    /// Starts sampling if it is not running. The first answer would otherwise
+    /// show lifetime averages, so two readings are taken before returning.
+    fn start(&self) -> Result<()> {
+        let _starting = self.starting.lock().unwrap();
+        if self.state.lock().unwrap().running {
+            return Ok(());
+        }
+        let mut sampler = Sampler::default();
+        sampler.apply(Readings::take()?);
+        std::thread::sleep(WARMUP);
+        sampler.apply(Readings::take()?);
+
+        let mut state = self.state.lock().unwrap();
+        state.sampler = sampler;
+        state.last_query = Some(Instant::now());
+        state.running = true;
+        let shared = Arc::clone(&self.state);
+        std::thread::spawn(move || sample_until_idle(shared));
+        Ok(())
+    }
+}
+
+fn sample_until_idle(state: Arc<Mutex<MonitorState>>) {
+    loop {
+        std::thread::sleep(INTERVAL);
+        {
+            let mut state = state.lock().unwrap();
+            if state.last_query.is_none_or(|t| t.elapsed() > IDLE_TIMEOUT) {
+                state.running = false;
+                return;
+            }
+        }
+        // On failure the previous reading stays current
+        if let Ok(readings) = Readings::take() {
+            state.lock().unwrap().sampler.apply(readings);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampler_measures_current_cpu() {
+        let me = std::process::id();
+        let mut sampler = Sampler::default();
+        sampler.apply(Readings::take().unwrap());
+        let first = sampler.samples.iter().find(|s| s.process.pid == me).unwrap();
+        assert_eq!(first.interval_ms, 0);
+
+        // Spin for a while: the next reading should see a busy process
+        let start = Instant::now();
+        let mut spins = 0u64;
+        while start.elapsed() < Duration::from_millis(400) {
+            spins = std::hint::black_box(spins + 1);
+        }
+        sampler.apply(Readings::take().unwrap());
+        let own = sampler.samples.iter().find(|s| s.process.pid == me).unwrap();
+        assert!(own.interval_ms >= 400);
+        assert!(own.process.cpu_percent > 25.0, "cpu_percent = {}", own.process.cpu_percent);
+
+        let filter = ProcessFilter { query: Some(own.process.name.to_uppercase()), ..Default::default() };
+        let matching = sampler.top(ProcessSort::Cpu, &filter, usize::MAX);
+        assert!(matching.iter().any(|s| s.process.pid == me));
+        assert!(matching.iter().all(|s| filter.matches(s)));
+
+        let by_memory = sampler.top(ProcessSort::Memory, &ProcessFilter::default(), 5);
+        assert!(by_memory.len() <= 5);
+        assert!(by_memory.windows(2).all(|w| w[0].process.rss_bytes >= w[1].process.rss_bytes));
+
+        let nobody = ProcessFilter { user: Some("no such user".into()), ..Default::default() };
+        assert!(sampler.top(ProcessSort::Io, &nobody, 5).is_empty());
+    }
+
+    #[test]
+    fn test_monitor_first_answer_is_measured() {
+        let me = std::process::id();
+        let monitor = ProcessMonitor::default();
+        let top = monitor.top(ProcessSort::Cpu, &ProcessFilter::default(), usize::MAX).unwrap();
+        let own = top.iter().find(|s| s.process.pid == me).unwrap();
+        assert!(own.interval_ms >= WARMUP.as_millis() as u64);
+        assert!(monitor.state.lock().unwrap().running);
+    }
+}
@@ -0,0 +1,1010 @@
+//! Running processes, read natively: `/proc` on Linux, libproc on macOS.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{OpenSealError, Result};
+
+/// Scheduler state of a process.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessState {
+    Running,
+    Sleeping,
+    /// Uninterruptible sleep, usually waiting on disk I/O
+    DiskSleep,
+    Stopped,
+    Zombie,
+    Idle,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    pub name: String,
+    /// Owner's user name, or the numeric uid if it has none
+    pub user: String,
+    /// Effective user id
+    pub uid: u32,
+    pub state: ProcessState,
+    pub threads: u32,
+    pub rss_bytes: u64,
+    pub vsz_bytes: u64,
+    /// Resident memory in MiB
+    pub memory_mb: f64,
+    /// CPU time used over the process's lifetime, as a share of one core
+    pub cpu_percent: f64,
+    /// Total user and system CPU time, in milliseconds
+    pub cpu_time_ms: u64,
+    /// Unix timestamp (seconds) the process started at
+    pub start_time: u64,
+    /// The full command line, or the bracketed name for kernel threads
+    pub command: String,
+    pub argv: Vec<String>,
+}
+
+/// Lists up to `limit` processes, busiest first.
+pub fn list_processes(limit: usize) -> Result<Vec<ProcessInfo>> {
+    let mut processes = all_processes()?;
+    processes.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent).then(a.pid.cmp(&b.pid)));
+    processes.truncate(limit);
+    Ok(processes)
+}
+
+/// Builds a `ProcessInfo` from the raw values every backend collects.
+struct RawProcess {
+    pid: u32,
+    ppid: u32,
+    /// Short executable name as the kernel records it (may be truncated)
+    comm: String,
+    uid: u32,
+    state: ProcessState,
+    threads: u32,
+    rss_bytes: u64,
+    vsz_bytes: u64,
+    cpu_time_ms: u64,
+    /// Start time in seconds since the Unix epoch, with sub-second precision
+    start_time: f64,
+    argv: Vec<String>,
+}
+
+impl RawProcess {
+    fn into_info(self, now: f64, users: &mut HashMap<u32, String>) -> ProcessInfo {
+        let elapsed = now - self.start_time;
+        let cpu_percent = if elapsed > 0.0 { self.cpu_time_ms as f64 / 10.0 / elapsed } else { 0.0 };
+        // The kernel truncates names (15 bytes on Linux); argv[0] usually has the full one
+        let name = self.argv.first()
+            .and_then(|arg0| arg0.rsplit('/').next())
+            .filter(|base| base.len() > self.comm.len() && base.starts_with(&self.comm))
+            .map_or_else(|| self.comm.clone(), str::to_string);
+        let command = if self.argv.is_empty() { format!("[{}]", self.comm) } else { self.argv.join(" ") };
+        ProcessInfo {
+            pid: self.pid,
+            ppid: self.ppid,
+            name,
+            user: users.entry(self.uid).or_insert_with(|| user_name(self.uid)).clone(),
+            uid: self.uid,
+            state: self.state,
+            threads: self.threads,
+            rss_bytes: self.rss_bytes,
+            vsz_bytes: self.vsz_bytes,
+            memory_mb: self.rss_bytes as f64 / (1024.0 * 1024.0),
+            cpu_percent,
+            cpu_time_ms: self.cpu_time_ms,
+            start_time: self.start_time as u64,
+            command,
+            argv: self.argv,
+        }
+    }
+}
+
+/// Looks up the name of `uid` in the user database.
+#[cfg(unix)]
+fn user_name(uid: u32) -> String {
+    use std::ffi::CStr;
+
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    let rc = unsafe { libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+    if rc != 0 || result.is_null() || pwd.pw_name.is_null() {
+        return uid.to_string();
+    }
+    unsafe { CStr::from_ptr(pwd.pw_name) }.to_string_lossy().into_owned()
+}
+
+#[cfg(not(unix))]
+fn user_name(uid: u32) -> String {
+    uid.to_string()
+}
+
+fn now_secs() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64()
+}
+
+/// Splits a NUL-separated argument block (as in `/proc/<pid>/cmdline`).
+fn split_args(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+// ── Linux: /proc ───────────────────────────────────────────────────────────
+
+/// Every process visible in `/proc`. Processes that exit while being read
+/// are left out.
+#[cfg(target_os = "linux")]
+pub fn all_processes() -> Result<Vec<ProcessInfo>> {
+    let entries = std::fs::read_dir("/proc").map_err(|e| OpenSealError::io("/proc", e))?;
+    let clock = ProcClock::read().map_err(|e| OpenSealError::io("/proc/stat", e))?;
+    let now = now_secs();
+    let mut users = HashMap::new();
+    Ok(entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| read_proc(pid, &clock))
+        .map(|raw| raw.into_info(now, &mut users))
+        .collect())
+}
+
+/// System constants needed to turn `/proc/<pid>/stat` fields into bytes and times.
+#[cfg(target_os = "linux")]
+struct ProcClock {
+    ticks_per_sec: u64,
+    page_size: u64,
+    /// Boot time in seconds since the Unix epoch
+    boot_time: u64,
+}
+
+#[cfg(target_os = "linux")]
+impl ProcClock {
+    fn read() -> std::io::Result<Self> {
+        let stat = std::fs::read_to_string("/proc/stat")?;
+        let boot_time = stat
+            .lines()
+            .find_map(|line| line.strip_prefix("btime "))
+            .and_then(|v| v.trim().parse().ok())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "no btime line"))?;
+        let sysconf = |name| u64::try_from(unsafe { libc::sysconf(name) }).ok().filter(|v| *v > 0);
+        Ok(ProcClock {
+            ticks_per_sec: sysconf(libc::_SC_CLK_TCK).unwrap_or(100),
+            page_size: sysconf(libc::_SC_PAGESIZE).unwrap_or(4096),
+            boot_time,
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_proc(pid: u32, clock: &ProcClock) -> Option<RawProcess> {
+    let dir = std::path::PathBuf::from(format!("/proc/{}", pid));
+    let stat = parse_stat(&std::fs::read_to_string(dir.join("stat")).ok()?)?;
+    let status = std::fs::read_to_string(dir.join("status")).ok()?;
+    let uid = status_uid(&status)?;
+    let argv = std::fs::read(dir.join("cmdline")).map(|b| split_args(&b)).unwrap_or_default();
+
+    let ticks_to_ms = |ticks: u64| ticks * 1000 / clock.ticks_per_sec;
+    Some(RawProcess {
+        pid,
+        ppid: stat.ppid,
+        comm: stat.comm,
+        uid,
+        state: stat.state,
+        threads: stat.threads,
+        rss_bytes: stat.rss_pages * clock.page_size,
+        vsz_bytes: stat.vsize,
+        cpu_time_ms: ticks_to_ms(stat.utime + stat.stime),
+        start_time: clock.boot_time as f64 + stat.start_ticks as f64 / clock.ticks_per_sec as f64,
+        argv,
+    })
+}
+
+/// Effective uid from `/proc/<pid>/status`.
+#[cfg(target_os = "linux")]
+fn status_uid(status: &str) -> Option<u32> {
+    // Uid: real, effective, saved, filesystem
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|ids| ids.split_whitespace().nth(1)?.parse().ok())
+}
+
+/// Owner and state of a single process, or `None` if it does not exist.
+#[cfg(target_os = "linux")]
+fn process_status(pid: u32) -> Option<(u32, ProcessState)> {
+    let stat = parse_stat(&std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)?;
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    Some((status_uid(&status)?, stat.state))
+}
+
+/// The fields of `/proc/<pid>/stat` we use.
+#[cfg(target_os = "linux")]
+struct ProcStat {
+    comm: String,
+    state: ProcessState,
+    ppid: u32,
+    utime: u64,
+    stime: u64,
+    threads: u32,
+    start_ticks: u64,
+    vsize: u64,
+    rss_pages: u64,
+}
+
+/// Parses `/proc/<pid>/stat`. The name is in parentheses and may itself
+/// contain spaces and parentheses, so fields are counted from the last `)`.
+#[cfg(target_os = "linux")]
+fn parse_stat(stat: &str) -> Option<ProcStat> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let fields: Vec<&str> = stat.get(close + 1..)?.split_whitespace().collect();
+    // Field numbers as in proc(5): the state is field 3
+    let field = |n: usize| fields.get(n - 3).copied();
+    let number = |n: usize| field(n)?.parse::<u64>().ok();
+    let state = match field(3)? {
+        "R" => ProcessState::Running,
+        "S" => ProcessState::Sleeping,
+        "D" => ProcessState::DiskSleep,
+        "T" | "t" => ProcessState::Stopped,
+        "Z" => ProcessState::Zombie,
+        "I" => ProcessState::Idle,
+        _ => ProcessState::Unknown,
+    };
+    Some(ProcStat {
+        comm: stat.get(open + 1..close)?.to_string(),
+        state,
+        ppid: number(4)? as u32,
+        utime: number(14)?,
+        stime: number(15)?,
+        threads: number(20)? as u32,
+        start_ticks: number(22)?,
+        vsize: number(23)?,
+        // Negative only in theory; treat it as nothing resident
+        rss_pages: field(24)?.parse::<i64>().ok()?.max(0) as u64,
+    })
+}
+
+// ── macOS: libproc ─────────────────────────────────────────────────────────
+
+/// Every process on the system. Memory, CPU and thread counts of other
+/// users' processes need privileges and read as zero without them.
+#[cfg(target_os = "macos")]
+pub fn all_processes() -> Result<Vec<ProcessInfo>> {
+    use std::mem::size_of;
+
+    let os_error = || OpenSealError::io("/", std::io::Error::last_os_error());
+    let count = unsafe { libc::proc_listallpids(std::ptr::null_mut(), 0) };
+    if count < 0 {
+        return Err(os_error());
+    }
+    // Room for processes started in between the two calls
+    let mut pids: Vec<libc::pid_t> = vec![0; count as usize + 64];
+    let size = (pids.len() * size_of::<libc::pid_t>()) as libc::c_int;
+    let count = unsafe { libc::proc_listallpids(pids.as_mut_ptr().cast(), size) };
+    if count < 0 {
+        return Err(os_error());
+    }
+    pids.truncate(count as usize);
+
+    let ticks_to_ns = mach_ticks_to_ns();
+    let now = now_secs();
+    let mut users = HashMap::new();
+    Ok(pids
+        .into_iter()
+        .filter(|&pid| pid > 0)
+        .filter_map(|pid| read_proc(pid, ticks_to_ns))
+        .map(|raw| raw.into_info(now, &mut users))
+        .collect())
+}
+
+/// Task CPU times are in Mach absolute time units; this converts them to ns.
+#[cfg(target_os = "macos")]
+#[allow(deprecated)] // the libc crate points to the mach2 crate for these
+fn mach_ticks_to_ns() -> f64 {
+    let mut timebase = libc::mach_timebase_info { numer: 0, denom: 0 };
+    if unsafe { libc::mach_timebase_info(&mut timebase) } != 0 || timebase.denom == 0 {
+        return 1.0;
+    }
+    timebase.numer as f64 / timebase.denom as f64
+}
+
+/// Reads one `proc_pidinfo` flavor of `pid`.
+#[cfg(target_os = "macos")]
+fn pid_info<T>(pid: libc::pid_t, flavor: libc::c_int) -> Option<T> {
+    let mut info: T = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<T>() as libc::c_int;
+    let read = unsafe { libc::proc_pidinfo(pid, flavor, 0, (&mut info as *mut T).cast(), size) };
+    (read == size).then_some(info)
+}
+
+#[cfg(target_os = "macos")]
+fn bsd_state(status: u32) -> ProcessState {
+    match status {
+        libc::SRUN => ProcessState::Running,
+        libc::SSLEEP => ProcessState::Sleeping,
+        libc::SSTOP => ProcessState::Stopped,
+        libc::SZOMB => ProcessState::Zombie,
+        libc::SIDL => ProcessState::Idle,
+        _ => ProcessState::Unknown,
+    }
+}
+
+/// Owner and state of a single process, or `None` if it does not exist.
+#[cfg(target_os = "macos")]
+fn process_status(pid: u32) -> Option<(u32, ProcessState)> {
+    let bsd = pid_info::<libc::proc_bsdinfo>(libc::pid_t::try_from(pid).ok()?, libc::PROC_PIDTBSDINFO)?;
+    Some((bsd.pbi_uid, bsd_state(bsd.pbi_status)))
+}
+
+#[cfg(target_os = "macos")]
+fn read_proc(pid: libc::pid_t, ticks_to_ns: f64) -> Option<RawProcess> {
+    // Task info is only readable for our own processes (or as root)
+    let (bsd, task) = match pid_info::<libc::proc_taskallinfo>(pid, libc::PROC_PIDTASKALLINFO) {
+        Some(all) => (all.pbsd, Some(all.ptinfo)),
+        None => (pid_info::<libc::proc_bsdinfo>(pid, libc::PROC_PIDTBSDINFO)?, None),
+    };
+    let text = |chars: &[libc::c_char]| {
+        unsafe { std::ffi::CStr::from_ptr(chars.as_ptr()) }.to_string_lossy().into_owned()
+    };
+    let comm = match text(&bsd.pbi_name) {
+        name if name.is_empty() => text(&bsd.pbi_comm),
+        name => name,
+    };
+    let cpu_ticks = task.map_or(0, |t| t.pti_total_user + t.pti_total_system);
+    Some(RawProcess {
+        pid: pid as u32,
+        ppid: bsd.pbi_ppid,
+        comm,
+        uid: bsd.pbi_uid,
+        state: bsd_state(bsd.pbi_status),
+        threads: task.map_or(0, |t| t.pti_threadnum.max(0) as u32),
+        rss_bytes: task.map_or(0, |t| t.pti_resident_size),
+        vsz_bytes: task.map_or(0, |t| t.pti_virtual_size),
+        cpu_time_ms: (cpu_ticks as f64 * ticks_to_ns / 1e6) as u64,
+        start_time: bsd.pbi_start_tvsec as f64 + bsd.pbi_start_tvusec as f64 / 1e6,
+        argv: process_args(pid),
+    })
+}
+
+/// Reads the argument vector of `pid` with `KERN_PROCARGS2`. The buffer
+/// holds argc, the executable path, padding, then the NUL-separated
+/// arguments and the environment.
+#[cfg(target_os = "macos")]
+fn process_args(pid: libc::pid_t) -> Vec<String> {
+    let mut mib = [libc::CTL_KERN, libc::KERN_PROCARGS2, pid];
+    let mut size: libc::size_t = 0;
+    let query = |buf: *mut libc::c_void, size: &mut libc::size_t, mib: &mut [libc::c_int; 3]| unsafe {
+        libc::sysctl(mib.as_mut_ptr(), 3, buf, size, std::ptr::null_mut(), 0)
+    };
+    if query(std::ptr::null_mut(), &mut size, &mut mib) != 0 || size < 4 {
+        return Vec::new();
+    }
+    let mut buf = vec![0u8; size];
+    if query(buf.as_mut_ptr().cast(), &mut size, &mut mib) != 0 || size < 4 {
+        return Vec::new();
+    }
+    buf.truncate(size);
+
+    let argc = i32::from_ne_bytes([buf[0], buf[1], buf[2], buf[3]]).max(0) as usize;
+    let rest = &buf[4..];
+    // Skip the executable path and the NULs padding it
+    let start = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+    let start = rest[start..].iter().position(|&b| b != 0).map_or(rest.len(), |p| start + p);
+    let mut argv = split_args(&rest[start..]);
+    argv.truncate(argc);
+    argv
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn process_status(_pid: u32) -> Option<(u32, ProcessState)> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn all_processes() -> Result<Vec<ProcessInfo>> {
+    Err(OpenSealError::io("/", std::io::Error::from(std::io::ErrorKind::Unsupported)))
+}
+
+// ── Signals ────────────────────────────────────────────────────────────────
+
+/// What `signal_process` does to a process.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessSignal {
+    /// SIGTERM, then SIGKILL if the process outlives the grace period
+    Terminate,
+    /// SIGKILL right away
+    Kill,
+    /// SIGSTOP
+    Suspend,
+    /// SIGCONT
+    Resume,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalOutcome {
+    /// The process is gone, or only a zombie is left for its parent to reap
+    Exited,
+    /// The signal was delivered and the process is still there
+    StillRunning,
+    /// The process belongs to another user, or the system refused the signal
+    PermissionDenied,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalResult {
+    pub pid: u32,
+    pub signal: ProcessSignal,
+    pub outcome: SignalOutcome,
+    /// Whether a `Terminate` had to fall back to SIGKILL
+    pub escalated: bool,
+}
+
+/// How long a process gets to disappear after SIGKILL.
+const KILL_WAIT: Duration = Duration::from_secs(1);
+const EXIT_POLL: Duration = Duration::from_millis(50);
+
+/// Sends `signal` to `pid` and reports what became of the process. For
+/// `Terminate` and `Kill` this blocks until the process exits, for at most
+/// `grace` plus a second.
+///
+/// Init, this process and (unless we run as root) other users' processes
+/// are never signaled.
+#[cfg(unix)]
+pub fn signal_process(pid: u32, signal: ProcessSignal, grace: Duration) -> Result<SignalResult> {
+    if pid <= 1 || pid == std::process::id() {
+        return Err(OpenSealError::ProtectedProcess(pid));
+    }
+    // Anything above pid_t's range would address a process group instead
+    let target = libc::pid_t::try_from(pid).map_err(|_| OpenSealError::ProcessNotFound(pid))?;
+    let (uid, state) = process_status(pid).ok_or(OpenSealError::ProcessNotFound(pid))?;
+
+    let euid = unsafe { libc::geteuid() };
+    let mut escalated = false;
+    let outcome = if state == ProcessState::Zombie {
+        SignalOutcome::Exited
+    } else if euid != 0 && uid != euid {
+        SignalOutcome::PermissionDenied
+    } else {
+        let exit_outcome = |limit| if wait_for_exit(pid, limit) { SignalOutcome::Exited } else { SignalOutcome::StillRunning };
+        match signal {
+            ProcessSignal::Terminate => send_signal(target, libc::SIGTERM).unwrap_or_else(|| {
+                // A stopped process only acts on SIGTERM once it runs again
+                if state == ProcessState::Stopped {
+                    send_signal(target, libc::SIGCONT);
+                }
+                if wait_for_exit(pid, grace) {
+                    return SignalOutcome::Exited;
+                }
+                send_signal(target, libc::SIGKILL).unwrap_or_else(|| {
+                    escalated = true;
+                    exit_outcome(KILL_WAIT)
+                })
+            }),
+            ProcessSignal::Kill => send_signal(target, libc::SIGKILL).unwrap_or_else(|| exit_outcome(KILL_WAIT)),
+            ProcessSignal::Suspend => send_signal(target, libc::SIGSTOP).unwrap_or(SignalOutcome::StillRunning),
+            ProcessSignal::Resume => send_signal(target, libc::SIGCONT).unwrap_or(SignalOutcome::StillRunning),
+        }
+    };
+    Ok(SignalResult { pid, signal, outcome, escalated })
+}
+
+#[cfg(not(unix))]
+pub fn signal_process(_pid: u32, _signal: ProcessSignal, _grace: Duration) -> Result<SignalResult> {
+    Err(OpenSealError::io("/", std::io::Error::from(std::io::ErrorKind::Unsupported)))
+}
+
+/// Sends `sig`, returning the final outcome if it could not be delivered.
+#[cfg(unix)]
+fn send_signal(pid: libc::pid_t, sig: libc::c_int) -> Option<SignalOutcome> {
+    if unsafe { libc::kill(pid, sig) } == 0 {
+        return None;
+    }
+    match std::io::Error::last_os_error().raw_os_error() {
+        Some(libc::ESRCH) => Some(SignalOutcome::Exited),
+        _ => Some(SignalOutcome::PermissionDenied),
+    }
+}
+
+/// Waits up to `limit` for `pid` to exit. A zombie counts as exited.
+#[cfg(unix)]
+fn wait_for_exit(pid: u32, limit: Duration) -> bool {
+    let start = Instant::now();
+    loop {
+        if process_status(pid).is_none_or(|(_, state)| state == ProcessState::Zombie) {
+            return true;
+        }
+        if start.elapsed() >= limit {
+            return false;
+        }
+        std::thread::sleep(EXIT_POLL);
+    }
+}
+
+// ── I/O and open files ─────────────────────────────────────────────────────
+
+/// Bytes a process has read from and written to storage since it started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct IoCounters {
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenFileKind {
+    File,
+    Directory,
+    Pipe,
+    Socket,
+    Device,
+    Other,
+}
+
+/// An open file descriptor.
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenFile {
+    pub fd: i32,
+    pub kind: OpenFileKind,
+    /// Path of the file, or a description such as `socket:[1234]` where there is none
+    pub path: String,
+    /// Size of a regular file
+    pub size_bytes: Option<u64>,
+    /// The file was deleted while open. Its space is only freed once every
+    /// descriptor on it is closed, and no directory scan can see it.
+    pub deleted: bool,
+}
+
+/// Storage I/O of `pid` so far. Reading other users' processes needs root.
+#[cfg(target_os = "linux")]
+pub fn process_io(pid: u32) -> Result<IoCounters> {
+    let path = format!("/proc/{}/io", pid);
+    let io = std::fs::read_to_string(&path).map_err(|e| proc_error(pid, &path, e))?;
+    let field = |name: &str| {
+        io.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|v| v.trim().parse().ok())
+            .ok_or_else(|| OpenSealError::InvalidData { path: path.clone().into(), message: format!("no {} field", name) })
+    };
+    Ok(IoCounters { read_bytes: field("read_bytes")?, write_bytes: field("write_bytes")? })
+}
+
+/// Lists the open file descriptors of `pid`, by number. Reading other
+/// users' processes needs root.
+#[cfg(target_os = "linux")]
+pub fn open_files(pid: u32) -> Result<Vec<OpenFile>> {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+    let dir = format!("/proc/{}/fd", pid);
+    let entries = std::fs::read_dir(&dir).map_err(|e| proc_error(pid, &dir, e))?;
+    let mut files: Vec<OpenFile> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let fd = entry.file_name().to_str()?.parse().ok()?;
+            // Closed in the meantime if these fail
+            let target = std::fs::read_link(entry.path()).ok()?.to_string_lossy().into_owned();
+            // Follows the link to the open file itself, even a deleted one
+            let meta = std::fs::metadata(entry.path()).ok();
+            let file_type = meta.as_ref().map(|m| m.file_type());
+            let kind = match file_type {
+                Some(t) if t.is_file() => OpenFileKind::File,
+                Some(t) if t.is_dir() => OpenFileKind::Directory,
+                Some(t) if t.is_fifo() => OpenFileKind::Pipe,
+                Some(t) if t.is_socket() => OpenFileKind::Socket,
+                Some(t) if t.is_char_device() || t.is_block_device() => OpenFileKind::Device,
+                _ => OpenFileKind::Other,
+            };
+            let regular = meta.filter(|m| m.is_file());
+            let deleted = regular.as_ref().is_some_and(|m| m.nlink() == 0);
+            let path = match target.strip_suffix(" (deleted)") {
+                Some(path) if deleted => path.to_string(),
+                _ => target,
+            };
+            Some(OpenFile { fd, kind, path, size_bytes: regular.map(|m| m.len()), deleted })
+        })
+        .collect();
+    files.sort_by_key(|f| f.fd);
+    Ok(files)
+}
+
+/// Maps an error reading `/proc/<pid>/...` to one about the process.
+#[cfg(target_os = "linux")]
+fn proc_error(pid: u32, path: &str, e: std::io::Error) -> OpenSealError {
+    match e.kind() {
+        std::io::ErrorKind::NotFound => OpenSealError::ProcessNotFound(pid),
+        std::io::ErrorKind::PermissionDenied => OpenSealError::ProcessAccessDenied(pid),
+        _ => OpenSealError::io(path, e),
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn process_io(pid: u32) -> Result<IoCounters> {
+    let target = libc::pid_t::try_from(pid).map_err(|_| OpenSealError::ProcessNotFound(pid))?;
+    let mut usage: libc::rusage_info_v2 = unsafe { std::mem::zeroed() };
+    let buffer = (&mut usage as *mut libc::rusage_info_v2).cast();
+    if unsafe { libc::proc_pid_rusage(target, libc::RUSAGE_INFO_V2, buffer) } != 0 {
+        return Err(libproc_error(pid));
+    }
+    Ok(IoCounters { read_bytes: usage.ri_diskio_bytesread, write_bytes: usage.ri_diskio_byteswritten })
+}
+
+/// Lists the open file descriptors of `pid`, by number. Reading other
+/// users' processes needs root.
+#[cfg(target_os = "macos")]
+pub fn open_files(pid: u32) -> Result<Vec<OpenFile>> {
+    use std::mem::size_of;
+
+    // <sys/proc_info.h>; not exported by the libc crate
+    const PROC_PIDFDVNODEPATHINFO: libc::c_int = 2;
+    #[repr(C)]
+    #[allow(dead_code)] // filled in by the kernel
+    struct ProcFileInfo {
+        fi_openflags: u32,
+        fi_status: u32,
+        fi_offset: libc::off_t,
+        fi_type: i32,
+        fi_guardflags: u32,
+    }
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct VnodeFdInfoWithPath {
+        pfi: ProcFileInfo,
+        pvip: libc::vnode_info_path,
+    }
+
+    let target = libc::pid_t::try_from(pid).map_err(|_| OpenSealError::ProcessNotFound(pid))?;
+    let needed = unsafe { libc::proc_pidinfo(target, libc::PROC_PIDLISTFDS, 0, std::ptr::null_mut(), 0) };
+    if needed <= 0 {
+        return Err(libproc_error(pid));
+    }
+    // Room for descriptors opened in between the two calls
+    let mut fds: Vec<libc::proc_fdinfo> =
+        vec![unsafe { std::mem::zeroed() }; needed as usize / size_of::<libc::proc_fdinfo>() + 16];
+    let size = (fds.len() * size_of::<libc::proc_fdinfo>()) as libc::c_int;
+    let read = unsafe { libc::proc_pidinfo(target, libc::PROC_PIDLISTFDS, 0, fds.as_mut_ptr().cast(), size) };
+    if read <= 0 {
+        return Err(libproc_error(pid));
+    }
+    fds.truncate(read as usize / size_of::<libc::proc_fdinfo>());
+
+    let mut files: Vec<OpenFile> = fds
+        .iter()
+        .filter_map(|fdinfo| {
+            let fd = fdinfo.proc_fd;
+            let (kind, path) = match fdinfo.proc_fdtype as libc::c_int {
+                libc::PROX_FDTYPE_VNODE => {
+                    let mut info: VnodeFdInfoWithPath = unsafe { std::mem::zeroed() };
+                    let size = size_of::<VnodeFdInfoWithPath>() as libc::c_int;
+                    let buffer = (&mut info as *mut VnodeFdInfoWithPath).cast();
+                    // Closed in the meantime if this fails
+                    if unsafe { libc::proc_pidfdinfo(target, fd, PROC_PIDFDVNODEPATHINFO, buffer, size) } != size {
+                        return None;
+                    }
+                    let stat = &info.pvip.vip_vi.vi_stat;
+                    let path = unsafe { std::ffi::CStr::from_ptr(info.pvip.vip_path.as_ptr().cast()) };
+                    let kind = match stat.vst_mode & libc::S_IFMT {
+                        libc::S_IFREG => OpenFileKind::File,
+                        libc::S_IFDIR => OpenFileKind::Directory,
+                        libc::S_IFIFO => OpenFileKind::Pipe,
+                        libc::S_IFSOCK => OpenFileKind::Socket,
+                        libc::S_IFCHR | libc::S_IFBLK => OpenFileKind::Device,
+                        _ => OpenFileKind::Other,
+                    };
+                    if kind == OpenFileKind::File {
+                        return Some(OpenFile {
+                            fd,
+                            kind,
+                            path: path.to_string_lossy().into_owned(),
+                            size_bytes: Some(stat.vst_size.max(0) as u64),
+                            deleted: stat.vst_nlink == 0,
+                        });
+                    }
+                    (kind, path.to_string_lossy().into_owned())
+                }
+                libc::PROX_FDTYPE_SOCKET => (OpenFileKind::Socket, "socket".to_string()),
+                libc::PROX_FDTYPE_PIPE => (OpenFileKind::Pipe, "pipe".to_string()),
+                libc::PROX_FDTYPE_KQUEUE => (OpenFileKind::Other, "kqueue".to_string()),
+                libc::PROX_FDTYPE_PSHM => (OpenFileKind::Other, "shared memory".to_string()),
+                libc::PROX_FDTYPE_PSEM => (OpenFileKind::Other, "semaphore".to_string()),
+                libc::PROX_FDTYPE_FSEVENTS => (OpenFileKind::Other, "fsevents".to_string()),
+                _ => (OpenFileKind::Other, String::new()),
+            };
+            Some(OpenFile { fd, kind, path, size_bytes: None, deleted: false })
+        })
+        .collect();
+    files.sort_by_key(|f| f.fd);
+    Ok(files)
+}
+
+/// Maps the error of a failed libproc call on `pid`.
+#[cfg(target_os = "macos")]
+fn libproc_error(pid: u32) -> OpenSealError {
+    let e = std::io::Error::last_os_error();
+    match e.raw_os_error() {
+        Some(libc::ESRCH) => OpenSealError::ProcessNotFound(pid),
+        Some(libc::EPERM) | Some(libc::EACCES) => OpenSealError::ProcessAccessDenied(pid),
+        _ => OpenSealError::io("/", e),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn process_io(_pid: u32) -> Result<IoCounters> {
+    Err(OpenSealError::io("/", std::io::Error::from(std::io::ErrorKind::Unsupported)))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn open_files(_pid: u32) -> Result<Vec<OpenFile>> {
+    Err(OpenSealError::io("/", std::io::Error::from(std::io::ErrorKind::Unsupported)))
+}
+
+// ── Tree ───────────────────────────────────────────────────────────────────
+
+/// A process with its descendants and their combined usage.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessNode {
+    #[serde(flatten)]
+    pub process: ProcessInfo,
+    /// CPU of this process, its helpers and all descendants
+    pub total_cpu_percent: f64,
+    pub total_rss_bytes: u64,
+    /// Processes in this subtree, this one and its helpers included
+    pub process_count: usize,
+    /// Helper processes folded into this node when collapsing
+    pub helper_pids: Vec<u32>,
+    /// Busiest first
+    pub children: Vec<ProcessNode>,
+}
+
+/// Arranges `processes` by parent, busiest first. Children of init (launchd
+/// on macOS) and processes whose parent is not listed become roots, so each
+/// root is one app or service with the totals of everything it spawned.
+///
+/// With `collapse`, a child from the same app bundle or executable as its
+/// parent (a browser's renderers, an Electron app's helpers) is folded into
+/// the parent, which adopts the helper's own children.
+pub fn process_tree(processes: Vec<ProcessInfo>, collapse: bool) -> Vec<ProcessNode> {
+    let listed: HashSet<u32> = processes.iter().map(|p| p.pid).collect();
+    let mut children: HashMap<u32, Vec<ProcessInfo>> = HashMap::new();
+    let mut roots = Vec::new();
+    for process in processes {
+        // pid 0 is its own parent on macOS
+        if process.ppid <= 1 || process.ppid == process.pid || !listed.contains(&process.ppid) {
+            roots.push(process);
+        } else {
+            children.entry(process.ppid).or_default().push(process);
+        }
+    }
+    let mut nodes: Vec<ProcessNode> = roots.into_iter().map(|p| build_node(p, &mut children, collapse)).collect();
+    sort_nodes(&mut nodes);
+    nodes
+}
+
+fn build_node(process: ProcessInfo, children: &mut HashMap<u32, Vec<ProcessInfo>>, collapse: bool) -> ProcessNode {
+    let app = collapse.then(|| app_key(&process).to_string());
+    let mut pending = children.remove(&process.pid).unwrap_or_default();
+    let mut node = ProcessNode {
+        total_cpu_percent: process.cpu_percent,
+        total_rss_bytes: process.rss_bytes,
+        process_count: 1,
+        helper_pids: Vec::new(),
+        children: Vec::new(),
+        process,
+    };
+    while let Some(child) = pending.pop() {
+        if app.as_deref().is_some_and(|app| app == app_key(&child)) {
+            node.total_cpu_percent += child.cpu_percent;
+            node.total_rss_bytes += child.rss_bytes;
+            node.process_count += 1;
+            node.helper_pids.push(child.pid);
+            pending.extend(children.remove(&child.pid).unwrap_or_default());
+        } else {
+            let child = build_node(child, children, collapse);
+            node.total_cpu_percent += child.total_cpu_percent;
+            node.total_rss_bytes += child.total_rss_bytes;
+            node.process_count += child.process_count;
+            node.children.push(child);
+        }
+    }
+    node.helper_pids.sort_unstable();
+    sort_nodes(&mut node.children);
+    node
+}
+
+fn sort_nodes(nodes: &mut [ProcessNode]) {
+    nodes.sort_by(|a, b| b.total_cpu_percent.total_cmp(&a.total_cpu_percent).then(a.process.pid.cmp(&b.process.pid)));
+}
+
+/// The app a process belongs to: the outermost `.app` bundle its executable
+/// lives in, or else the executable itself.
+fn app_key(process: &ProcessInfo) -> &str {
+    let exe = process.argv.first().map_or(process.name.as_str(), String::as_str);
+    match exe.find(".app/") {
+        Some(end) => &exe[..end + 4],
+        None => exe,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_stat() {
+        // A name with spaces and parentheses must not shift the fields
+        let line = "4242 (Web (Content) 2) S 1 4242 4242 0 -1 4194560 900 0 0 0 150 50 0 0 20 0 7 0 1234 104857600 2560 18446744073709551615";
+        let stat = parse_stat(line).unwrap();
+        assert_eq!(stat.comm, "Web (Content) 2");
+        assert_eq!(stat.state, ProcessState::Sleeping);
+        assert_eq!(stat.ppid, 1);
+        assert_eq!((stat.utime, stat.stime), (150, 50));
+        assert_eq!(stat.threads, 7);
+        assert_eq!(stat.start_ticks, 1234);
+        assert_eq!(stat.vsize, 104_857_600);
+        assert_eq!(stat.rss_pages, 2560);
+        assert!(parse_stat("4242 (truncated) S 1").is_none());
+    }
+
+    #[test]
+    fn test_lists_own_process() {
+        let me = std::process::id();
+        let processes = all_processes().unwrap();
+        let own = processes.iter().find(|p| p.pid == me).unwrap();
+        assert_eq!(own.ppid, std::os::unix::process::parent_id());
+        assert_eq!(own.uid, unsafe { libc::geteuid() });
+        assert!(own.rss_bytes > 0);
+        assert!(own.threads >= 1);
+        assert_eq!(own.argv, std::env::args().collect::<Vec<_>>());
+        assert!(own.start_time > 0 && own.start_time as f64 <= now_secs());
+
+        let top = list_processes(3).unwrap();
+        assert!(top.len() <= 3);
+        assert!(top.windows(2).all(|w| w[0].cpu_percent >= w[1].cpu_percent));
+    }
+
+    /// Starts `sleep` with SIGTERM ignored when `ignore_term` is set, and
+    /// waits until it runs.
+    fn spawn_sleeper(ignore_term: bool) -> std::process::Child {
+        let trap = if ignore_term { "trap '' TERM; " } else { "" };
+        let child = std::process::Command::new("sh")
+            .args(["-c", &format!("{}exec sleep 30", trap)])
+            .spawn()
+            .unwrap();
+        let start = Instant::now();
+        while !all_processes().unwrap().iter().any(|p| p.pid == child.id() && p.name == "sleep") {
+            assert!(start.elapsed() < Duration::from_secs(5), "sleep did not start");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        child
+    }
+
+    #[test]
+    fn test_signal_process() {
+        let grace = Duration::from_secs(2);
+        assert_eq!(signal_process(1, ProcessSignal::Kill, grace).unwrap_err(), OpenSealError::ProtectedProcess(1));
+        let me = std::process::id();
+        assert_eq!(signal_process(me, ProcessSignal::Suspend, grace).unwrap_err(), OpenSealError::ProtectedProcess(me));
+        assert_eq!(signal_process(u32::MAX, ProcessSignal::Kill, grace).unwrap_err(), OpenSealError::ProcessNotFound(u32::MAX));
+        // Other users' processes are off limits, unless we run as root. A
+        // container may have no root-owned process besides init to try.
+        if unsafe { libc::geteuid() } != 0 {
+            let others = all_processes().unwrap().into_iter().find(|p| p.uid == 0 && p.pid > 1);
+            if let Some(others) = others {
+                let denied = signal_process(others.pid, ProcessSignal::Kill, grace).unwrap();
+                assert_eq!(denied.outcome, SignalOutcome::PermissionDenied);
+            }
+        }
+
+        let mut child = spawn_sleeper(false);
+        let pid = child.id();
+        let suspended = signal_process(pid, ProcessSignal::Suspend, grace).unwrap();
+        assert_eq!(suspended.outcome, SignalOutcome::StillRunning);
+        let start = Instant::now();
+        while process_status(pid).unwrap().1 != ProcessState::Stopped {
+            assert!(start.elapsed() < Duration::from_secs(5), "process was not stopped");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(signal_process(pid, ProcessSignal::Resume, grace).unwrap().outcome, SignalOutcome::StillRunning);
+
+        let terminated = signal_process(pid, ProcessSignal::Terminate, grace).unwrap();
+        assert_eq!(terminated.outcome, SignalOutcome::Exited);
+        assert!(!terminated.escalated);
+        child.wait().unwrap();
+
+        // A process that ignores SIGTERM is killed once the grace period is over
+        let mut stubborn = spawn_sleeper(true);
+        let terminated = signal_process(stubborn.id(), ProcessSignal::Terminate, Duration::from_millis(200)).unwrap();
+        assert_eq!(terminated.outcome, SignalOutcome::Exited);
+        assert!(terminated.escalated);
+        stubborn.wait().unwrap();
+    }
+
+    fn fake_process(pid: u32, ppid: u32, exe: &str, cpu_percent: f64, rss_bytes: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            ppid,
+            name: exe.rsplit('/').next().unwrap().to_string(),
+            user: "me".into(),
+            uid: 501,
+            state: ProcessState::Running,
+            threads: 1,
+            rss_bytes,
+            vsz_bytes: rss_bytes,
+            memory_mb: 0.0,
+            cpu_percent,
+            cpu_time_ms: 0,
+            start_time: 0,
+            command: exe.into(),
+            argv: vec![exe.into()],
+        }
+    }
+
+    #[test]
+    fn test_process_tree() {
+        let chrome = "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome";
+        let helper = "/Applications/Google Chrome.app/Contents/Frameworks/Helpers/Google Chrome Helper.app/Contents/MacOS/Google Chrome Helper";
+        let processes = vec![
+            fake_process(1, 0, "/sbin/launchd", 0.5, 10),
+            fake_process(100, 1, chrome, 5.0, 300),
+            fake_process(101, 100, helper, 20.0, 200),
+            fake_process(102, 101, helper, 10.0, 100),
+            fake_process(103, 101, "/usr/bin/python3", 1.0, 50),
+            fake_process(200, 1, "/usr/sbin/sshd", 0.0, 5),
+            // Its parent is gone
+            fake_process(300, 299, "/usr/bin/sleep", 0.0, 1),
+        ];
+
+        let tree = process_tree(processes.clone(), false);
+        assert_eq!(tree.iter().map(|n| n.process.pid).collect::<Vec<_>>(), vec![100, 1, 200, 300]);
+        let app = &tree[0];
+        assert_eq!(app.total_cpu_percent, 36.0);
+        assert_eq!(app.total_rss_bytes, 650);
+        assert_eq!(app.process_count, 4);
+        assert_eq!(app.children[0].children.iter().map(|n| n.process.pid).collect::<Vec<_>>(), vec![102, 103]);
+
+        // Helpers fold into the app; the helper's python child moves up to it
+        let tree = process_tree(processes, true);
+        let app = &tree[0];
+        assert_eq!(app.helper_pids, vec![101, 102]);
+        assert_eq!((app.total_cpu_percent, app.total_rss_bytes, app.process_count), (36.0, 650, 4));
+        assert_eq!(app.children.len(), 1);
+        assert_eq!(app.children[0].process.pid, 103);
+        assert!(tree[1].children.is_empty());
+    }
+
+    #[test]
+    fn test_io_and_open_files() {
+        use std::io::Write;
+
+        let me = std::process::id();
+        assert!(process_io(me).is_ok());
+        assert_eq!(process_io(u32::MAX).unwrap_err(), OpenSealError::ProcessNotFound(u32::MAX));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("held.log");
+        let mut held = std::fs::File::create(&path).unwrap();
+        held.write_all(&[0u8; 4096]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let files = open_files(me).unwrap();
+        assert!(files.windows(2).all(|w| w[0].fd < w[1].fd));
+        let file = files.iter().find(|f| f.path.ends_with("held.log")).unwrap();
+        assert_eq!(file.kind, OpenFileKind::File);
+        assert_eq!(file.size_bytes, Some(4096));
+        assert!(file.deleted);
+        assert_eq!(open_files(u32::MAX).unwrap_err(), OpenSealError::ProcessNotFound(u32::MAX));
+        drop(held);
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(split_args(b"/bin/sleep\x0010\x00"), vec!["/bin/sleep", "10"]);
+        assert!(split_args(b"").is_empty());
+    }
+}
@@ -0,0 +1,1964 @@
+//! Surf 核心扫描与分析引擎库
+//! 
+//! 提供文件系统扫描、目录树构建、统计分析等核心能力。
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::collections::{BinaryHeap, HashMap};
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use rayon;
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{OpenSealError, Result};
+
+/// 扫描进度回调的触发间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 扫描结果中保留的错误条目上限，超出部分只计数
+const MAX_SCAN_ERRORS: usize = 100;
+
+/// 扫描缓存文件格式版本，版本不符的缓存整体作废
+const SCAN_CACHE_VERSION: u32 = 1;
+
+/// 目录 mtime 距扫描开始不足该时长时不写入缓存，避免同一时间粒度内的修改被漏检
+const CACHE_MTIME_SLACK: Duration = Duration::from_secs(2);
+
+/// 扫描请求参数
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanRequest {
+    /// 扫描起始根目录
+    pub root_path: PathBuf,
+    /// 并发扫描线程数（默认逻辑核心数）
+    pub threads: Option<u16>,
+    /// 最小文件大小过滤（字节）
+    pub min_size: Option<u64>,
+    /// 排除规则（glob 模式）
+    pub exclude_patterns: Vec<String>,
+    /// 时间分析阈值天数（识别陈旧文件）
+    pub stale_days: Option<u32>,
+    /// Top N 大文件数量限制（默认20）
+    pub limit: Option<usize>,
+    /// 统计、排序与过滤所用的文件大小口径（默认逻辑大小）
+    pub size_mode: SizeMode,
+    /// 是否跟随符号链接（默认否，链接本身按文件计）；跟随时按 inode 检测环路
+    pub follow_symlinks: bool,
+    /// 是否只扫描根目录所在的文件系统（跳过挂载的其他卷）
+    pub one_file_system: bool,
+    /// 增量扫描缓存文件路径；设置后 mtime 未变的目录直接复用上次读取的条目。
+    /// 原地改写文件不会更新目录 mtime，因此复用目录中的文件大小为缓存时的值。
+    /// 跟随符号链接时不使用缓存
+    pub cache_path: Option<PathBuf>,
+}
+
+/// 文件大小口径
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeMode {
+    /// 逻辑大小（`metadata.len()`，与 `ls -l` 一致）
+    #[default]
+    Apparent,
+    /// 实际占用的磁盘空间（`st_blocks * 512`，与 `du`/`df` 一致）
+    Allocated,
+}
+
+impl ScanRequest {
+    /// 创建一个新的扫描请求
+    pub fn new<P: Into<PathBuf>>(root_path: P) -> Self {
+        Self {
+            root_path: root_path.into(),
+            threads: None,
+            min_size: None,
+            exclude_patterns: Vec::new(),
+            stale_days: None,
+            limit: None,
+            size_mode: SizeMode::Apparent,
+            follow_symlinks: false,
+            one_file_system: false,
+            cache_path: None,
+        }
+    }
+}
+
+/// 扫描进度信息
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanProgress {
+    /// 任务状态
+    pub state: ScanState,
+    /// 已扫描文件数
+    pub scanned_files: u64,
+    /// 已遍历字节数
+    pub scanned_bytes: u64,
+    /// 进度百分比 (0.0 - 1.0)
+    pub progress: f64,
+    /// 预计剩余时间（秒）
+    pub eta_seconds: Option<u64>,
+}
+
+impl ScanProgress {
+    /// 创建一个尚无扫描数据的进度（用于排队、失败等状态）
+    pub fn new(state: ScanState) -> Self {
+        Self {
+            state,
+            scanned_files: 0,
+            scanned_bytes: 0,
+            progress: 0.0,
+            eta_seconds: None,
+        }
+    }
+}
+
+/// 扫描任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ScanState {
+    /// 任务排队中
+    Queued,
+    /// 扫描进行中
+    Running,
+    /// 扫描已完成
+    Completed,
+    /// 任务已取消
+    Canceled,
+    /// 任务失败
+    Failed,
+}
+
+/// 扫描结果摘要
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanSummary {
+    /// 扫描根路径
+    pub root_path: PathBuf,
+    /// 总文件数
+    pub total_files: u64,
+    /// 总目录数
+    pub total_dirs: u64,
+    /// 总大小（字节，按请求的 size_mode 统计）
+    pub total_size_bytes: u64,
+    /// 总逻辑大小（字节）
+    pub total_apparent_bytes: u64,
+    /// 总实际占用磁盘空间（字节）
+    pub total_allocated_bytes: u64,
+    /// 通过硬链接共享、因此未重复计入总大小的字节数
+    pub hardlink_shared_bytes: u64,
+    /// 扫描中遇到的错误总数（可能多于 ScanResult::errors 中保留的条数）
+    pub error_count: u64,
+    /// 因无法读取而未扫描的字节数估算（按已扫描目录和文件的平均大小推算）
+    pub unscanned_bytes_estimate: u64,
+    /// 从增量扫描缓存复用的目录数
+    pub dirs_reused: u64,
+    /// 从磁盘读取的目录数
+    pub dirs_read: u64,
+    /// 扫描耗时（秒）
+    pub elapsed_seconds: f64,
+    /// 扫描是否被取消（为 true 时结果只包含取消前已收集的部分数据）
+    pub canceled: bool,
+}
+
+/// 文件条目信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEntry {
+    /// 文件路径
+    pub path: PathBuf,
+    /// 文件大小（字节，按请求的 size_mode 统计）
+    pub size_bytes: u64,
+    /// 逻辑大小（字节）
+    pub apparent_bytes: u64,
+    /// 实际占用磁盘空间（字节）
+    pub allocated_bytes: u64,
+    /// 最后修改时间
+    pub last_modified: Option<SystemTime>,
+    /// 文件扩展名（不含点）
+    pub extension: Option<String>,
+}
+
+impl Ord for FileEntry {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // 主要按文件大小降序排列（更大的文件排前面）
+        // 但为了在最小堆中使用，我们实际上需要升序排列，因此这里实现升序
+        // 后续会用 Reverse 包装来反转比较
+        self.size_bytes.cmp(&other.size_bytes)
+            .then_with(|| self.path.cmp(&other.path))
+    }
+}
+
+impl PartialOrd for FileEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for FileEntry {}
+
+impl PartialEq for FileEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.size_bytes == other.size_bytes && self.path == other.path
+    }
+}
+
+/// 文件类型统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionStat {
+    /// 文件扩展名（不含点）
+    pub extension: String,
+    /// 文件数量
+    pub file_count: u64,
+    /// 总大小（字节）
+    pub total_size_bytes: u64,
+}
+
+/// 扫描完整结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanResult {
+    /// 扫描摘要
+    pub summary: ScanSummary,
+    /// Top N 大文件列表（按大小降序）
+    pub top_files: Vec<FileEntry>,
+    /// 按扩展名统计
+    pub by_extension: Vec<ExtensionStat>,
+    /// 陈旧文件列表（超过阈值未访问/修改）
+    pub stale_files: Vec<FileEntry>,
+    /// 目录树（以扫描根目录为根，大小为子树累计值）
+    pub tree: DirNode,
+    /// 被跳过的目录（其他文件系统的挂载点、环路），按路径排序
+    pub skipped: Vec<SkippedDir>,
+    /// 扫描中遇到的错误（最多 MAX_SCAN_ERRORS 条），按路径排序
+    pub errors: Vec<ScanError>,
+    /// 去重台账，供实时监控沿用；不随结果序列化
+    #[serde(skip)]
+    pub(crate) ledger: ScanLedger,
+}
+
+/// 扫描中无法读取的目录或条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanError {
+    /// 出错的路径
+    pub path: PathBuf,
+    /// 错误类型
+    pub kind: ScanErrorKind,
+}
+
+/// 扫描错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ScanErrorKind {
+    /// 权限不足
+    PermissionDenied,
+    /// 路径在扫描过程中消失
+    NotFound,
+    /// 其他 I/O 错误
+    Other,
+}
+
+impl From<&std::io::Error> for ScanErrorKind {
+    fn from(e: &std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::PermissionDenied => ScanErrorKind::PermissionDenied,
+            std::io::ErrorKind::NotFound => ScanErrorKind::NotFound,
+            _ => ScanErrorKind::Other,
+        }
+    }
+}
+
+/// 单个目录的浅层扫描结果
+#[derive(Debug, Clone)]
+pub struct ShallowDir {
+    /// 目录自身（不含子目录）的统计
+    pub stats: DirStats,
+    /// 需要进入的直接子目录（已去除被排除、位于其他文件系统或构成环路的）
+    pub subdirs: Vec<PathBuf>,
+}
+
+/// 单个目录自身（不含子目录）的统计
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DirStats {
+    /// 文件数
+    pub files: u64,
+    /// 文件总大小（字节，按请求的 size_mode 统计）
+    pub size_bytes: u64,
+    /// 逻辑大小（字节）
+    pub apparent_bytes: u64,
+    /// 实际占用磁盘空间（字节）
+    pub allocated_bytes: u64,
+    /// 硬链接已在别处计入、因此未重复计入的字节数
+    pub shared_bytes: u64,
+}
+
+/// 扫描的去重台账：各目录自身的统计、计入过的 inode 与进入过的目录。
+/// 实时监控据此做增量更新，使硬链接去重、环路与跨文件系统的判定与完整扫描一致
+#[derive(Debug, Clone, Default)]
+pub struct ScanLedger {
+    /// 每个目录自身的统计
+    pub(crate) dirs: HashMap<PathBuf, DirStats>,
+    /// 已计入文件的 (dev, inode) -> 计入它的目录
+    pub(crate) inodes: HashMap<(u64, u64), PathBuf>,
+    /// 已进入目录的 (dev, inode) -> 目录路径
+    pub(crate) visited: HashMap<(u64, u64), PathBuf>,
+    /// 根目录所在设备号
+    pub(crate) root_dev: Option<u64>,
+}
+
+/// 扫描中被跳过的目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedDir {
+    /// 目录路径
+    pub path: PathBuf,
+    /// 跳过原因
+    pub reason: SkipReason,
+}
+
+/// 目录被跳过的原因
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SkipReason {
+    /// 位于其他文件系统（one_file_system 模式下的挂载点）
+    OtherFilesystem,
+    /// 目录已被访问过（符号链接环路或重复挂载）
+    Loop,
+}
+
+/// 目录树节点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirNode {
+    /// 目录路径
+    pub path: PathBuf,
+    /// 目录名（如 `/` 这类没有文件名的路径则为完整路径）
+    pub name: String,
+    /// 子树累计大小（字节，经过 min_size 过滤后）
+    pub size_bytes: u64,
+    /// 子树累计文件数
+    pub file_count: u64,
+    /// 子树累计目录数（不含自身）
+    pub dir_count: u64,
+    /// 子目录节点（按大小降序）
+    pub children: Vec<DirNode>,
+}
+
+impl DirNode {
+    /// 返回截断到指定深度的副本，depth 为 0 时只保留当前节点
+    ///
+    /// 被截掉的子树不影响累计大小和计数，前端可据此按需下钻。
+    pub fn truncated(&self, depth: usize) -> DirNode {
+        DirNode {
+            path: self.path.clone(),
+            name: self.name.clone(),
+            size_bytes: self.size_bytes,
+            file_count: self.file_count,
+            dir_count: self.dir_count,
+            children: if depth == 0 {
+                Vec::new()
+            } else {
+                self.children.iter().map(|c| c.truncated(depth - 1)).collect()
+            },
+        }
+    }
+
+    /// 在子树中查找路径为 `path` 的节点
+    pub fn find(&self, path: &Path) -> Option<&DirNode> {
+        if self.path == path {
+            return Some(self);
+        }
+        let rest = path.strip_prefix(&self.path).ok()?;
+        let next = rest.components().next()?;
+        let child_path = self.path.join(next);
+        self.children.iter().find(|c| c.path == child_path)?.find(path)
+    }
+}
+
+/// 扫描过程中用于收集统计信息的内部结构
+struct ScanCollector {
+    /// 总文件数（经过 min_size 过滤后）
+    total_files: u64,
+    /// 总目录数
+    total_dirs: u64,
+    /// 总大小（字节，经过 min_size 过滤后）
+    total_size_bytes: u64,
+    /// 按扩展名统计的映射表
+    by_extension_map: HashMap<String, ExtensionStat>,
+    /// Top N 大文件列表（按大小降序），内部使用向量维护，上限为 TOP_FILES_LIMIT
+    top_files: Vec<FileEntry>,
+    /// 陈旧文件列表
+    stale_files: Vec<FileEntry>,
+    /// 当前时间，用于陈旧文件判断
+    now: SystemTime,
+}
+
+/// 目录自身的条目：文件元数据与子目录名，既用于本次统计也写入增量扫描缓存
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DirListing {
+    files: Vec<CachedFile>,
+    subdirs: Vec<std::ffi::OsString>,
+    /// 读取中途出错或被取消，这样的条目不写入缓存
+    #[serde(skip)]
+    incomplete: bool,
+}
+
+/// 统计一个文件所需的元数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedFile {
+    name: std::ffi::OsString,
+    apparent_bytes: u64,
+    allocated_bytes: u64,
+    modified: Option<SystemTime>,
+    /// (dev, inode)，非 Unix 平台为 None
+    inode: Option<(u64, u64)>,
+    nlink: u64,
+}
+
+impl CachedFile {
+    fn new(name: std::ffi::OsString, meta: &std::fs::Metadata) -> Self {
+        Self {
+            name,
+            apparent_bytes: meta.len(),
+            allocated_bytes: allocated_size(meta),
+            modified: meta.modified().ok(),
+            inode: inode_key(meta),
+            nlink: link_count(meta),
+        }
+    }
+
+    /// 多链接文件的 (dev, inode) 标识；链接数为 1 的文件无需去重，返回 None
+    fn hardlink_key(&self) -> Option<(u64, u64)> {
+        if self.nlink > 1 { self.inode } else { None }
+    }
+
+    /// 去重用的 (dev, inode)：跟随符号链接时任何文件都可能重复出现，否则只需考虑多链接文件
+    fn dedup_key(&self, follow_symlinks: bool) -> Option<(u64, u64)> {
+        if follow_symlinks { self.inode } else { self.hardlink_key() }
+    }
+}
+
+/// 增量扫描缓存：目录路径 -> (目录 mtime, 目录条目)
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScanCache {
+    version: u32,
+    dirs: HashMap<PathBuf, (SystemTime, DirListing)>,
+}
+
+impl ScanCache {
+    /// 读取缓存文件；文件不存在、损坏或版本不符时返回空缓存
+    fn load(path: &Path) -> Self {
+        std::fs::File::open(path)
+            .ok()
+            .and_then(|f| bincode::deserialize_from::<_, ScanCache>(std::io::BufReader::new(f)).ok())
+            .filter(|cache| cache.version == SCAN_CACHE_VERSION)
+            .unwrap_or_default()
+    }
+
+    /// 先写临时文件再重命名，避免中断时留下半个缓存文件
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // 每次写入独立的临时文件，并发保存互不覆盖
+        let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+        let mut writer = std::io::BufWriter::new(tmp.as_file_mut());
+        bincode::serialize_into(&mut writer, self).map_err(std::io::Error::other)?;
+        std::io::Write::flush(&mut writer)?;
+        drop(writer);
+        tmp.persist(path).map_err(|e| e.error)?;
+        Ok(())
+    }
+}
+
+/// 用于并行扫描的原子计数器
+struct AtomicCounters {
+    files: AtomicU64,
+    dirs: AtomicU64,
+    size: AtomicU64,
+    apparent_size: AtomicU64,
+    allocated_size: AtomicU64,
+    /// 已发现（已排入遍历任务）的目录数，用于估算进度
+    dirs_discovered: AtomicU64,
+    /// 已读取完条目的目录数，用于估算进度
+    dirs_done: AtomicU64,
+    /// Top N 大文件限制
+    limit: usize,
+    /// Top N 大文件堆（最小堆，使用 Reverse 包装 FileEntry 实现）
+    top_files: Arc<Mutex<BinaryHeap<Reverse<FileEntry>>>>,
+    /// 扩展名统计映射：扩展名 -> (文件数, 总大小)
+    extensions: Arc<Mutex<HashMap<String, (u64, u64)>>>,
+    /// 陈旧文件列表
+    stale_files: Arc<Mutex<Vec<FileEntry>>>,
+    /// 每个目录自身（不含子目录）的统计
+    dir_stats: Arc<Mutex<HashMap<PathBuf, DirStats>>>,
+    /// 已计入文件的 (dev, inode) -> 计入它的目录，保证每个 inode 只统计一次
+    seen_inodes: Arc<Mutex<HashMap<(u64, u64), PathBuf>>>,
+    /// 已进入目录的 (dev, inode) -> 目录路径，用于检测环路
+    visited_dirs: Arc<Mutex<HashMap<(u64, u64), PathBuf>>>,
+    /// 根目录所在设备号，用于 one_file_system 模式
+    root_dev: Option<u64>,
+    /// 被跳过的目录
+    skipped: Arc<Mutex<Vec<SkippedDir>>>,
+    /// 保留的错误条目（最多 MAX_SCAN_ERRORS 条）
+    errors: Arc<Mutex<Vec<ScanError>>>,
+    /// 错误总数
+    error_count: AtomicU64,
+    /// 无法读取的目录数
+    failed_dirs: AtomicU64,
+    /// 无法读取元数据的条目数
+    failed_entries: AtomicU64,
+    /// 上次扫描留下的缓存；为 None 表示本次扫描不使用缓存
+    cache: Option<HashMap<PathBuf, (SystemTime, DirListing)>>,
+    /// 本次扫描生成的缓存条目
+    new_cache: Arc<Mutex<HashMap<PathBuf, (SystemTime, DirListing)>>>,
+    /// 扫描开始时间，用于判断目录 mtime 是否足够旧、可以写入缓存
+    started: SystemTime,
+    /// 从缓存复用的目录数
+    dirs_reused: AtomicU64,
+    /// 从磁盘读取的目录数
+    dirs_read: AtomicU64,
+    /// 因硬链接而跳过的字节数
+    hardlink_shared: AtomicU64,
+}
+
+impl AtomicCounters {
+    fn new(limit: usize) -> Self {
+        Self {
+            files: AtomicU64::new(0),
+            dirs: AtomicU64::new(0),
+            size: AtomicU64::new(0),
+            apparent_size: AtomicU64::new(0),
+            allocated_size: AtomicU64::new(0),
+            dirs_discovered: AtomicU64::new(1), // 根目录
+            dirs_done: AtomicU64::new(0),
+            limit,
+            top_files: Arc::new(Mutex::new(BinaryHeap::with_capacity(limit))),
+            extensions: Arc::new(Mutex::new(HashMap::new())),
+            stale_files: Arc::new(Mutex::new(Vec::new())),
+            dir_stats: Arc::new(Mutex::new(HashMap::new())),
+            seen_inodes: Arc::new(Mutex::new(HashMap::new())),
+            visited_dirs: Arc::new(Mutex::new(HashMap::new())),
+            root_dev: None,
+            skipped: Arc::new(Mutex::new(Vec::new())),
+            errors: Arc::new(Mutex::new(Vec::new())),
+            error_count: AtomicU64::new(0),
+            failed_dirs: AtomicU64::new(0),
+            failed_entries: AtomicU64::new(0),
+            cache: None,
+            new_cache: Arc::new(Mutex::new(HashMap::new())),
+            started: SystemTime::now(),
+            dirs_reused: AtomicU64::new(0),
+            dirs_read: AtomicU64::new(0),
+            hardlink_shared: AtomicU64::new(0),
+        }
+    }
+    
+    fn add_file_with_extension(&self, extension: Option<String>, size: u64) {
+        let ext = extension.unwrap_or_else(|| "no_extension".to_string());
+        let mut map = self.extensions.lock().unwrap();
+        let entry = map.entry(ext).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += size;
+    }
+
+    fn add_file_to_top_list(&self, entry: &FileEntry) {
+        // 过滤大小为0的文件
+        if entry.size_bytes == 0 {
+            return;
+        }
+        let mut heap = self.top_files.lock().unwrap();
+        if heap.len() < self.limit {
+            heap.push(Reverse(entry.clone()));
+        } else {
+            // 堆已满，比较新文件与堆顶（当前堆中最小的文件）
+            if let Some(top) = heap.peek() {
+                if entry.size_bytes > top.0.size_bytes {
+                    heap.pop(); // 移除堆顶最小文件
+                    heap.push(Reverse(entry.clone()));
+                }
+            }
+        }
+    }
+
+    fn extensions_to_vec(&self) -> Vec<ExtensionStat> {
+        let map = self.extensions.lock().unwrap();
+        let mut vec: Vec<ExtensionStat> = map
+            .iter()
+            .map(|(ext, &(file_count, total_size_bytes))| ExtensionStat {
+                extension: ext.clone(),
+                file_count,
+                total_size_bytes,
+            })
+            .collect();
+        // 按总大小降序排序，如果大小相同则按文件数降序
+        vec.sort_by(|a, b| {
+            b.total_size_bytes
+                .cmp(&a.total_size_bytes)
+                .then_with(|| b.file_count.cmp(&a.file_count))
+        });
+        vec
+    }
+
+    fn top_files_to_vec(&self) -> Vec<FileEntry> {
+        let heap = self.top_files.lock().unwrap();
+        // 将堆转换为向量，并反转顺序（从大到小）
+        let mut vec: Vec<FileEntry> = heap.iter().map(|rev| rev.0.clone()).collect();
+        // 由于堆是最小堆，堆顶是最小元素，但iter顺序不确定，需要按大小降序排序
+        vec.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| b.path.cmp(&a.path)));
+        vec
+    }
+
+    fn add_stale_file(&self, entry: FileEntry) {
+        let mut vec = self.stale_files.lock().unwrap();
+        vec.push(entry);
+    }
+
+    fn stale_files_to_vec(&self) -> Vec<FileEntry> {
+        let vec = self.stale_files.lock().unwrap();
+        vec.clone()
+    }
+
+    /// 判断子目录是否需要跳过：one_file_system 模式下位于其他设备，或已进入过（环路）
+    fn dir_skip_reason(&self, dir: &Path, meta: &std::fs::Metadata, one_file_system: bool) -> Option<SkipReason> {
+        let mut visited = self.visited_dirs.lock().unwrap();
+        dir_skip_reason(dir, meta, one_file_system, self.root_dev, &mut visited)
+    }
+
+    fn add_skipped(&self, path: PathBuf, reason: SkipReason) {
+        let mut vec = self.skipped.lock().unwrap();
+        vec.push(SkippedDir { path, reason });
+    }
+
+    fn skipped_to_vec(&self) -> Vec<SkippedDir> {
+        let mut vec = self.skipped.lock().unwrap().clone();
+        vec.sort_by(|a, b| a.path.cmp(&b.path));
+        vec
+    }
+
+    /// 记录一个扫描错误；is_dir 表示整个目录无法读取，否则为单个条目
+    fn add_error(&self, path: PathBuf, error: &std::io::Error, is_dir: bool) {
+        if is_dir {
+            self.failed_dirs.fetch_add(1, Ordering::SeqCst);
+        } else {
+            self.failed_entries.fetch_add(1, Ordering::SeqCst);
+        }
+        self.error_count.fetch_add(1, Ordering::SeqCst);
+        let mut vec = self.errors.lock().unwrap();
+        if vec.len() < MAX_SCAN_ERRORS {
+            vec.push(ScanError { path, kind: error.into() });
+        }
+    }
+
+    fn errors_to_vec(&self) -> Vec<ScanError> {
+        let mut vec = self.errors.lock().unwrap().clone();
+        vec.sort_by(|a, b| a.path.cmp(&b.path));
+        vec
+    }
+
+    /// 估算未扫描的字节数：无法读取的目录按已读目录的平均大小计，无法读取的条目按平均文件大小计
+    fn unscanned_bytes_estimate(&self) -> u64 {
+        let size = self.size.load(Ordering::SeqCst);
+        let files = self.files.load(Ordering::SeqCst);
+        let failed_dirs = self.failed_dirs.load(Ordering::SeqCst);
+        let read_dirs = self.dirs.load(Ordering::SeqCst).saturating_sub(failed_dirs);
+        let per_dir = size.checked_div(read_dirs).unwrap_or(0);
+        let per_file = size.checked_div(files).unwrap_or(0);
+        failed_dirs * per_dir + self.failed_entries.load(Ordering::SeqCst) * per_file
+    }
+
+    /// 目录 mtime 与缓存记录一致时返回缓存的条目
+    fn cached_listing(&self, dir: &Path, mtime: SystemTime) -> Option<DirListing> {
+        let (cached_mtime, listing) = self.cache.as_ref()?.get(dir)?;
+        (*cached_mtime == mtime).then(|| listing.clone())
+    }
+
+    /// 记录目录条目以写入新缓存；不完整的条目、刚修改过的目录和非 UTF-8 路径不缓存
+    fn store_listing(&self, dir: &Path, mtime: SystemTime, listing: &DirListing) {
+        if self.cache.is_none() || listing.incomplete || dir.to_str().is_none() {
+            return;
+        }
+        if mtime + CACHE_MTIME_SLACK > self.started {
+            return;
+        }
+        let mut map = self.new_cache.lock().unwrap();
+        map.insert(dir.to_path_buf(), (mtime, listing.clone()));
+    }
+
+    /// 合并新旧缓存并写入：根目录以外的旧条目保留，根目录下的条目以本次扫描为准
+    fn save_cache(&self, path: &Path, root: &Path) -> std::io::Result<()> {
+        let mut dirs: HashMap<PathBuf, (SystemTime, DirListing)> = self
+            .cache
+            .iter()
+            .flatten()
+            .filter(|(dir, _)| !dir.starts_with(root))
+            .map(|(dir, entry)| (dir.clone(), entry.clone()))
+            .collect();
+        dirs.extend(self.new_cache.lock().unwrap().drain());
+        ScanCache { version: SCAN_CACHE_VERSION, dirs }.save(path)
+    }
+
+    /// 统计一个文件：依次应用排除规则、最小大小过滤与 inode 去重，
+    /// 计入总数、扩展名、Top N、陈旧文件列表以及所在目录的统计 `own`
+    fn count_file(
+        &self,
+        path: PathBuf,
+        file: &CachedFile,
+        request: &ScanRequest,
+        exclude_patterns: &[Pattern],
+        own: &mut DirStats,
+    ) {
+        let size = match request.size_mode {
+            SizeMode::Apparent => file.apparent_bytes,
+            SizeMode::Allocated => file.allocated_bytes,
+        };
+
+        // 文件匹配排除规则则跳过
+        if is_excluded(&path, exclude_patterns) {
+            return;
+        }
+
+        // 应用 min-size 过滤
+        if let Some(min_size) = request.min_size {
+            if size < min_size {
+                return;
+            }
+        }
+
+        // 同一 inode 已计入过（其他硬链接，或跟随符号链接时的重复目标）则跳过，硬链接记为共享字节
+        let dir = path.parent().unwrap_or(&path);
+        if file.dedup_key(request.follow_symlinks).is_some_and(|key| !self.first_link(key, dir)) {
+            if file.hardlink_key().is_some() {
+                self.hardlink_shared.fetch_add(size, Ordering::SeqCst);
+                own.shared_bytes += size;
+            }
+            return;
+        }
+
+        // 增加文件计数和大小
+        own.files += 1;
+        own.size_bytes += size;
+        own.apparent_bytes += file.apparent_bytes;
+        own.allocated_bytes += file.allocated_bytes;
+        self.files.fetch_add(1, Ordering::SeqCst);
+        self.size.fetch_add(size, Ordering::SeqCst);
+        self.apparent_size.fetch_add(file.apparent_bytes, Ordering::SeqCst);
+        self.allocated_size.fetch_add(file.allocated_bytes, Ordering::SeqCst);
+        // 提取扩展名
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|s| s.to_lowercase());
+        self.add_file_with_extension(extension.clone(), size);
+        // 添加到 Top N 大文件列表
+        let file_entry = FileEntry {
+            path,
+            size_bytes: size,
+            apparent_bytes: file.apparent_bytes,
+            allocated_bytes: file.allocated_bytes,
+            last_modified: file.modified,
+            extension,
+        };
+        self.add_file_to_top_list(&file_entry);
+
+        // 检查是否为陈旧文件
+        if let Some(stale_days) = request.stale_days {
+            if let Some(last_modified) = file.modified {
+                if let Ok(duration) = SystemTime::now().duration_since(last_modified) {
+                    if duration.as_secs() >= (stale_days as u64) * 24 * 60 * 60 {
+                        self.add_stale_file(file_entry);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 记录一个 inode 由目录 `dir` 计入，若它此前已被计入则返回 false
+    fn first_link(&self, key: (u64, u64), dir: &Path) -> bool {
+        let mut map = self.seen_inodes.lock().unwrap();
+        match map.entry(key) {
+            std::collections::hash_map::Entry::Occupied(_) => false,
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert(dir.to_path_buf());
+                true
+            }
+        }
+    }
+
+    fn add_dir_stat(&self, dir: PathBuf, stats: DirStats) {
+        let mut map = self.dir_stats.lock().unwrap();
+        map.insert(dir, stats);
+    }
+
+    /// 取出去重台账（在生成目录树之后调用）
+    fn take_ledger(&self) -> ScanLedger {
+        ScanLedger {
+            dirs: std::mem::take(&mut *self.dir_stats.lock().unwrap()),
+            inodes: std::mem::take(&mut *self.seen_inodes.lock().unwrap()),
+            visited: std::mem::take(&mut *self.visited_dirs.lock().unwrap()),
+            root_dev: self.root_dev,
+        }
+    }
+
+    /// 由各目录自身的统计自底向上汇总出整棵目录树
+    fn dir_tree(&self, root: &Path) -> DirNode {
+        dir_tree_from_stats(root, &self.dir_stats.lock().unwrap())
+    }
+
+    /// 生成当前进度快照
+    ///
+    /// 总量未知，进度按已完成目录数 / 已发现目录数估算；`floor` 为上次上报的进度，
+    /// 用于保证进度单调不回退。运行中进度最多到 0.99，完成时才为 1.0。
+    fn to_progress(&self, state: ScanState, elapsed: Duration, floor: f64) -> ScanProgress {
+        let scanned_files = self.files.load(Ordering::SeqCst);
+        let scanned_bytes = self.size.load(Ordering::SeqCst);
+        if state == ScanState::Completed {
+            return ScanProgress {
+                state,
+                scanned_files,
+                scanned_bytes,
+                progress: 1.0,
+                eta_seconds: Some(0),
+            };
+        }
+
+        let discovered = self.dirs_discovered.load(Ordering::SeqCst).max(1);
+        let done = self.dirs_done.load(Ordering::SeqCst);
+        let progress = (done as f64 / discovered as f64).min(0.99).max(floor);
+        let eta_seconds = if progress > 0.0 {
+            Some((elapsed.as_secs_f64() * (1.0 - progress) / progress).round() as u64)
+        } else {
+            None
+        };
+        ScanProgress {
+            state,
+            scanned_files,
+            scanned_bytes,
+            progress,
+            eta_seconds,
+        }
+    }
+
+    fn to_summary(&self, root_path: PathBuf, elapsed_seconds: f64, canceled: bool) -> ScanSummary {
+        ScanSummary {
+            root_path,
+            total_files: self.files.load(Ordering::SeqCst),
+            total_dirs: self.dirs.load(Ordering::SeqCst),
+            total_size_bytes: self.size.load(Ordering::SeqCst),
+            total_apparent_bytes: self.apparent_size.load(Ordering::SeqCst),
+            total_allocated_bytes: self.allocated_size.load(Ordering::SeqCst),
+            hardlink_shared_bytes: self.hardlink_shared.load(Ordering::SeqCst),
+            error_count: self.error_count.load(Ordering::SeqCst),
+            unscanned_bytes_estimate: self.unscanned_bytes_estimate(),
+            dirs_reused: self.dirs_reused.load(Ordering::SeqCst),
+            dirs_read: self.dirs_read.load(Ordering::SeqCst),
+            elapsed_seconds,
+            canceled,
+        }
+    }
+}
+/// 核心扫描引擎
+pub struct Scanner {
+    /// 取消标志，置位后正在进行的扫描会尽快停止并返回部分结果
+    cancel_flag: Arc<AtomicBool>,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scanner {
+    /// 创建一个新的扫描器实例
+    pub fn new() -> Self {
+        Self {
+            cancel_flag: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// 返回共享的取消标志，可在其他线程中置位以取消扫描
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancel_flag)
+    }
+
+    /// 请求取消当前扫描
+    pub fn cancel(&self) {
+        self.cancel_flag.store(true, Ordering::SeqCst);
+    }
+
+    /// 是否已请求取消
+    pub fn is_canceled(&self) -> bool {
+        self.cancel_flag.load(Ordering::SeqCst)
+    }
+    
+    /// 同步扫描指定目录，返回扫描结果
+    pub fn scan_sync(&self, request: &ScanRequest) -> Result<ScanResult> {
+        self.scan_with_progress(request, |_| {})
+    }
+
+    /// 同步扫描指定目录，并在扫描过程中周期性回调进度
+    ///
+    /// 回调在独立的上报线程中每隔 `PROGRESS_INTERVAL` 触发一次（状态为 `Running`），
+    /// 扫描结束后在调用线程上以 `Completed`（或被取消时的 `Canceled`）状态再触发一次。
+    pub fn scan_with_progress<F>(&self, request: &ScanRequest, on_progress: F) -> Result<ScanResult>
+    where
+        F: Fn(&ScanProgress) + Sync,
+    {
+        let start_time = SystemTime::now();
+        
+        // 验证根目录存在且可访问
+        if !request.root_path.exists() {
+            return Err(OpenSealError::NotFound(request.root_path.clone()));
+        }
+        
+        // 配置 rayon 线程池
+        let threads = request.threads.unwrap_or(0); // 0 表示使用默认值
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(if threads > 0 { threads as usize } else { rayon::current_num_threads() })
+            .build()?;
+        
+        let limit = request.limit.unwrap_or(20);
+        let mut counters = AtomicCounters::new(limit);
+        counters.started = start_time;
+        if let Ok(root_meta) = std::fs::metadata(&request.root_path) {
+            counters.root_dev = inode_key(&root_meta).map(|(dev, _)| dev);
+        }
+        // 跟随符号链接时目录内容还取决于链接目标，不使用缓存
+        let cache_path = request.cache_path.as_deref().filter(|_| !request.follow_symlinks);
+        if let Some(path) = cache_path {
+            counters.cache = Some(ScanCache::load(path).dirs);
+        }
+
+        let exclude_patterns = compile_excludes(request);
+        
+        // 使用线程池执行并行遍历，同时由上报线程周期性回调进度
+        let done = AtomicBool::new(false);
+        std::thread::scope(|s| {
+            let reporter = s.spawn(|| {
+                let mut last_progress = 0.0;
+                loop {
+                    std::thread::park_timeout(PROGRESS_INTERVAL);
+                    if done.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let elapsed = start_time.elapsed().unwrap_or_default();
+                    let progress = counters.to_progress(ScanState::Running, elapsed, last_progress);
+                    last_progress = progress.progress;
+                    on_progress(&progress);
+                }
+            });
+            pool.scope(|scope| {
+                self.parallel_walk_dir(scope, request.root_path.clone(), &counters, request, &exclude_patterns);
+            });
+            done.store(true, Ordering::SeqCst);
+            reporter.thread().unpark();
+        });
+        
+        let elapsed = start_time.elapsed().unwrap_or_default();
+        let canceled = self.is_canceled();
+        // 取消的扫描不完整，不更新缓存；缓存写入失败不影响本次结果
+        if let (Some(path), false) = (cache_path, canceled) {
+            let _ = counters.save_cache(path, &request.root_path);
+        }
+        let final_state = if canceled { ScanState::Canceled } else { ScanState::Completed };
+        on_progress(&counters.to_progress(final_state, elapsed, 0.0));
+        
+        Ok(ScanResult {
+            summary: counters.to_summary(request.root_path.clone(), elapsed.as_secs_f64(), canceled),
+            top_files: counters.top_files_to_vec(),
+            by_extension: counters.extensions_to_vec(),
+            stale_files: counters.stale_files_to_vec(),
+            tree: counters.dir_tree(&request.root_path),
+            skipped: counters.skipped_to_vec(),
+            errors: counters.errors_to_vec(),
+            ledger: counters.take_ledger(),
+        })
+    }
+    
+    fn walk_dir(
+        dir: &Path,
+        total_files: &mut u64,
+        total_dirs: &mut u64,
+        total_size: &mut u64,
+    ) -> std::io::Result<()> {
+        if dir.is_dir() {
+            *total_dirs += 1;
+            for entry in std::fs::read_dir(dir)? {
+                let entry = entry?;
+                let path = entry.path();
+                
+                if path.is_dir() {
+                    Self::walk_dir(&path, total_files, total_dirs, total_size)?;
+                } else {
+                    *total_files += 1;
+                    *total_size += entry.metadata().map(|m| m.len()).unwrap_or(0);
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// 并行遍历目录树（内部实现）
+    fn parallel_walk_dir<'scope>(
+        &'scope self,
+        scope: &rayon::Scope<'scope>,
+        dir: PathBuf,
+        counters: &'scope AtomicCounters,
+        request: &'scope ScanRequest,
+        exclude_patterns: &'scope [Pattern],
+    ) {
+        // 已取消则不再进入新目录
+        if self.is_canceled() {
+            return;
+        }
+
+        // 检查是否为目录（跟随符号链接，与父目录中的判定一致）
+        let dir_meta = match std::fs::metadata(&dir) {
+            Ok(meta) if meta.is_dir() => meta,
+            _ => {
+                counters.dirs_done.fetch_add(1, Ordering::SeqCst);
+                return;
+            }
+        };
+
+        // 其他文件系统的挂载点与已进入过的目录（环路）不再遍历
+        if let Some(reason) = counters.dir_skip_reason(&dir, &dir_meta, request.one_file_system) {
+            counters.add_skipped(dir, reason);
+            counters.dirs_done.fetch_add(1, Ordering::SeqCst);
+            return;
+        }
+        
+        // 增加目录计数
+        counters.dirs.fetch_add(1, Ordering::SeqCst);
+
+        // 目录 mtime 与缓存一致时直接复用缓存的条目，否则从磁盘读取
+        let mtime = dir_meta.modified().ok();
+        let listing = match mtime.and_then(|mtime| counters.cached_listing(&dir, mtime)) {
+            Some(listing) => {
+                counters.dirs_reused.fetch_add(1, Ordering::SeqCst);
+                listing
+            }
+            None => match self.read_listing(&dir, counters, request) {
+                Ok(listing) => {
+                    counters.dirs_read.fetch_add(1, Ordering::SeqCst);
+                    listing
+                }
+                // 无法读取的目录记录错误，仍作为空节点保留在目录树中
+                Err(e) => {
+                    counters.add_error(dir.clone(), &e, true);
+                    counters.add_dir_stat(dir, DirStats::default());
+                    counters.dirs_done.fetch_add(1, Ordering::SeqCst);
+                    return;
+                }
+            },
+        };
+        if let Some(mtime) = mtime {
+            counters.store_listing(&dir, mtime, &listing);
+        }
+        
+        // 收集子目录，同时累计当前目录自身的文件数与大小
+        let mut subdirs = Vec::new();
+        for name in &listing.subdirs {
+            let path = dir.join(name);
+            // 目录匹配排除规则则跳过整棵子树
+            if !is_excluded(&path, exclude_patterns) {
+                subdirs.push(path);
+            }
+        }
+        let mut own = DirStats::default();
+        for file in &listing.files {
+            counters.count_file(dir.join(&file.name), file, request, exclude_patterns, &mut own);
+        }
+        
+        counters.add_dir_stat(dir, own);
+        counters.dirs_discovered.fetch_add(subdirs.len() as u64, Ordering::SeqCst);
+        counters.dirs_done.fetch_add(1, Ordering::SeqCst);
+        
+        // 为每个子目录生成并行任务
+        for subdir in subdirs {
+            let counters = counters; // 捕获引用
+            let request = request; // 捕获引用
+            scope.spawn(move |scope| {
+                self.parallel_walk_dir(scope, subdir, counters, request, exclude_patterns);
+            });
+        }
+    }
+
+    /// 从磁盘读取目录条目及每个文件的元数据；单个条目的错误记入 counters
+    fn read_listing(&self, dir: &Path, counters: &AtomicCounters, request: &ScanRequest) -> std::io::Result<DirListing> {
+        let entries = std::fs::read_dir(dir)?;
+
+        let mut listing = DirListing::default();
+        for entry in entries {
+            if self.is_canceled() {
+                listing.incomplete = true;
+                break;
+            }
+            let entry = match entry {
+                Ok(e) => e,
+                Err(e) => {
+                    counters.add_error(dir.to_path_buf(), &e, false);
+                    listing.incomplete = true;
+                    continue;
+                }
+            };
+            let path = entry.path();
+            let file_type = match entry.file_type() {
+                Ok(t) => t,
+                Err(e) => {
+                    counters.add_error(path, &e, false);
+                    listing.incomplete = true;
+                    continue;
+                }
+            };
+            // 默认不跟随符号链接，链接本身按文件计；跟随时按目标处理，悬空链接仍按链接本身计
+            let followed = if file_type.is_symlink() && request.follow_symlinks {
+                std::fs::metadata(&path).ok()
+            } else {
+                None
+            };
+            let is_dir = followed.as_ref().map_or(file_type.is_dir(), |m| m.is_dir());
+            if is_dir {
+                listing.subdirs.push(entry.file_name());
+                continue;
+            }
+            let metadata = match followed {
+                Some(meta) => meta,
+                None => match entry.metadata() {
+                    Ok(meta) => meta,
+                    Err(e) => {
+                        counters.add_error(path, &e, false);
+                        listing.incomplete = true;
+                        continue;
+                    }
+                },
+            };
+            listing.files.push(CachedFile::new(entry.file_name(), &metadata));
+        }
+        Ok(listing)
+    }
+
+    /// 只统计单个目录自身的文件（不进入子目录），规则与完整扫描相同；供实时监控增量更新使用
+    ///
+    /// `ledger` 为完整扫描留下的台账：本目录此前计入的 inode 先释放再重新计入，
+    /// 已由其他目录计入的 inode 不再计入；子目录按台账判定环路与跨文件系统，
+    /// 保留下来的子目录记入台账。
+    pub fn scan_dir_shallow(&self, dir: &Path, request: &ScanRequest, ledger: &mut ScanLedger) -> Result<ShallowDir> {
+        let counters = AtomicCounters::new(0);
+        let listing = self.read_listing(dir, &counters, request).map_err(|e| OpenSealError::io(dir, e))?;
+        let exclude_patterns = compile_excludes(request);
+
+        ledger.inodes.retain(|_, owner| owner != dir);
+        {
+            let mut seen = counters.seen_inodes.lock().unwrap();
+            for key in listing.files.iter().filter_map(|f| f.dedup_key(request.follow_symlinks)) {
+                if let Some(owner) = ledger.inodes.get(&key) {
+                    seen.insert(key, owner.clone());
+                }
+            }
+        }
+        let mut stats = DirStats::default();
+        for file in &listing.files {
+            counters.count_file(dir.join(&file.name), file, request, &exclude_patterns, &mut stats);
+        }
+        ledger.inodes.extend(counters.seen_inodes.lock().unwrap().drain());
+
+        let subdirs = listing
+            .subdirs
+            .iter()
+            .map(|name| dir.join(name))
+            .filter(|path| !is_excluded(path, &exclude_patterns))
+            .filter(|path| {
+                // 与完整扫描相同：读不到元数据的子目录留给遍历时处理
+                let Ok(meta) = std::fs::metadata(path) else { return true };
+                // 记录的路径已不再指向该目录（删除或移走后 inode 被复用）时，记录作废
+                if let Some(key) = inode_key(&meta) {
+                    let stale = ledger.visited.get(&key).is_some_and(|entered_as| {
+                        std::fs::metadata(entered_as).ok().and_then(|m| inode_key(&m)) != Some(key)
+                    });
+                    if stale {
+                        ledger.visited.remove(&key);
+                    }
+                }
+                dir_skip_reason(path, &meta, request.one_file_system, ledger.root_dev, &mut ledger.visited).is_none()
+            })
+            .collect();
+        Ok(ShallowDir { stats, subdirs })
+    }
+}
+
+/// 判断子目录是否需要跳过：one_file_system 模式下位于其他设备，或已由其他路径进入过（环路）；
+/// 否则将它记入 `visited`
+fn dir_skip_reason(
+    dir: &Path,
+    meta: &std::fs::Metadata,
+    one_file_system: bool,
+    root_dev: Option<u64>,
+    visited: &mut HashMap<(u64, u64), PathBuf>,
+) -> Option<SkipReason> {
+    if one_file_system {
+        if let (Some(root_dev), Some((dev, _))) = (root_dev, inode_key(meta)) {
+            if dev != root_dev {
+                return Some(SkipReason::OtherFilesystem);
+            }
+        }
+    }
+    let key = inode_key(meta)?;
+    let entered_as = visited.entry(key).or_insert_with(|| dir.to_path_buf());
+    if entered_as.as_path() == dir { None } else { Some(SkipReason::Loop) }
+}
+
+/// 由各目录自身（不含子目录）的统计构建以 `root` 为根的目录树
+pub(crate) fn dir_tree_from_stats(root: &Path, stats: &HashMap<PathBuf, DirStats>) -> DirNode {
+    let mut children_map: HashMap<&Path, Vec<&Path>> = HashMap::new();
+    for path in stats.keys() {
+        if path.as_path() == root {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            children_map.entry(parent).or_default().push(path.as_path());
+        }
+    }
+    build_dir_node(root, stats, &children_map)
+}
+
+/// 递归构建目录树节点，子目录按累计大小降序排列
+fn build_dir_node(
+    path: &Path,
+    stats: &HashMap<PathBuf, DirStats>,
+    children_map: &HashMap<&Path, Vec<&Path>>,
+) -> DirNode {
+    let own = stats.get(path).copied().unwrap_or_default();
+    let (mut file_count, mut size_bytes) = (own.files, own.size_bytes);
+    let mut dir_count = 0;
+    let mut children: Vec<DirNode> = children_map
+        .get(path)
+        .map(|paths| {
+            paths
+                .iter()
+                .map(|child| build_dir_node(child, stats, children_map))
+                .collect()
+        })
+        .unwrap_or_default();
+    for child in &children {
+        file_count += child.file_count;
+        size_bytes += child.size_bytes;
+        dir_count += child.dir_count + 1;
+    }
+    children.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| a.path.cmp(&b.path)));
+
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+
+    DirNode {
+        path: path.to_path_buf(),
+        name,
+        size_bytes,
+        file_count,
+        dir_count,
+        children,
+    }
+}
+
+/// 文件实际占用的磁盘空间（已分配块数 * 512），非 Unix 平台退化为逻辑大小
+pub fn allocated_size(meta: &std::fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        meta.blocks() * 512
+    }
+    #[cfg(not(unix))]
+    {
+        meta.len()
+    }
+}
+
+/// 文件或目录的 (dev, inode) 标识，非 Unix 平台返回 None
+fn inode_key(meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some((meta.dev(), meta.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = meta;
+        None
+    }
+}
+
+/// 文件的硬链接数，非 Unix 平台视为 1
+fn link_count(meta: &std::fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        meta.nlink()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = meta;
+        1
+    }
+}
+
+/// 预编译排除规则（glob 模式）；非法模式将被忽略
+fn compile_excludes(request: &ScanRequest) -> Vec<Pattern> {
+    request
+        .exclude_patterns
+        .iter()
+        .filter_map(|p| Pattern::new(p).ok())
+        .collect()
+}
+
+/// 判断路径是否匹配任一排除模式
+fn is_excluded(path: &Path, patterns: &[Pattern]) -> bool {
+    // 使用绝对或相对路径进行匹配，glob::Pattern 支持路径分隔符
+    for pat in patterns {
+        if pat.matches_path(path) {
+            return true;
+        }
+    }
+    false
+}
+
+/// 便捷函数：快速扫描指定路径
+pub fn scan_path<P: Into<PathBuf>>(path: P) -> Result<ScanResult> {
+    let request = ScanRequest::new(path);
+    let scanner = Scanner::new();
+    scanner.scan_sync(&request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_scan_request_new() {
+        let req = ScanRequest::new("/tmp");
+        assert_eq!(req.root_path, PathBuf::from("/tmp"));
+        assert!(req.threads.is_none());
+        assert!(req.min_size.is_none());
+        assert!(req.exclude_patterns.is_empty());
+        assert!(req.stale_days.is_none());
+    }
+    
+    #[test]
+    fn test_scan_sync_empty_dir() {
+        let dir = tempdir().unwrap();
+        let request = ScanRequest::new(dir.path());
+        let scanner = Scanner::new();
+        let result = scanner.scan_sync(&request).unwrap();
+        
+        assert_eq!(result.summary.total_files, 0);
+        assert_eq!(result.summary.total_dirs, 1); // 目录本身
+        assert_eq!(result.summary.total_size_bytes, 0);
+        assert!(result.summary.elapsed_seconds >= 0.0);
+    }
+    
+    #[test]
+    fn test_scan_sync_with_files() {
+        let dir = tempdir().unwrap();
+        
+        // 创建几个文件
+        let file1_path = dir.path().join("file1.txt");
+        let mut file1 = File::create(&file1_path).unwrap();
+        file1.write_all(b"Hello").unwrap();
+        
+        let file2_path = dir.path().join("file2.txt");
+        let mut file2 = File::create(&file2_path).unwrap();
+        file2.write_all(b"World!").unwrap();
+        
+        // 创建一个子目录
+        let subdir = dir.path().join("subdir");
+        fs::create_dir(&subdir).unwrap();
+        
+        let request = ScanRequest::new(dir.path());
+        let scanner = Scanner::new();
+        let result = scanner.scan_sync(&request).unwrap();
+        
+        assert_eq!(result.summary.total_files, 2);
+        assert_eq!(result.summary.total_dirs, 2); // 根目录 + subdir
+        assert_eq!(result.summary.total_size_bytes, 11); // 5 + 6
+    }
+    
+    #[test]
+    fn test_scan_path_convenience() {
+        let dir = tempdir().unwrap();
+        let result = scan_path(dir.path()).unwrap();
+        assert_eq!(result.summary.total_files, 0);
+    }
+    
+    #[test]
+    fn test_scan_sync_multithreaded() {
+        // 创建一个包含多个文件和子目录的测试目录树
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        
+        // 创建一些文件
+        for i in 0..10 {
+            let file_path = root.join(format!("file{}.txt", i));
+            let mut file = File::create(&file_path).unwrap();
+            file.write_all(format!("Content {}", i).as_bytes()).unwrap();
+        }
+        
+        // 创建一些子目录
+        for i in 0..5 {
+            let subdir = root.join(format!("dir{}", i));
+            fs::create_dir(&subdir).unwrap();
+            
+            // 在子目录中创建文件
+            for j in 0..3 {
+                let file_path = subdir.join(format!("subfile{}.txt", j));
+                let mut file = File::create(&file_path).unwrap();
+                file.write_all(format!("Sub content {}-{}", i, j).as_bytes()).unwrap();
+            }
+        }
+        
+        // 使用单线程扫描（threads = 1）作为基准
+        let mut request1 = ScanRequest::new(root);
+        request1.threads = Some(1);
+        let scanner = Scanner::new();
+        let result1 = scanner.scan_sync(&request1).unwrap();
+        
+        // 使用多线程扫描（默认线程数）
+        let mut request2 = ScanRequest::new(root);
+        request2.threads = None; // 使用默认（逻辑核心数）
+        let result2 = scanner.scan_sync(&request2).unwrap();
+        
+        // 使用明确指定线程数（例如 2）
+        let mut request3 = ScanRequest::new(root);
+        request3.threads = Some(2);
+        let result3 = scanner.scan_sync(&request3).unwrap();
+        
+        // 验证所有扫描结果的总计一致
+        assert_eq!(result1.summary.total_files, result2.summary.total_files);
+        assert_eq!(result1.summary.total_dirs, result2.summary.total_dirs);
+        assert_eq!(result1.summary.total_size_bytes, result2.summary.total_size_bytes);
+        
+        assert_eq!(result1.summary.total_files, result3.summary.total_files);
+        assert_eq!(result1.summary.total_dirs, result3.summary.total_dirs);
+        assert_eq!(result1.summary.total_size_bytes, result3.summary.total_size_bytes);
+        
+        // 验证具体数值（根据创建的文件和目录）
+        // 总文件数：10个根目录文件 + 5个子目录 * 3个文件 = 25
+        // 总目录数：根目录 + 5个子目录 = 6
+        assert_eq!(result1.summary.total_files, 25);
+        assert_eq!(result1.summary.total_dirs, 6);
+        
+        // 文件大小：每个文件内容长度不同，但我们可以验证总大小 > 0
+        assert!(result1.summary.total_size_bytes > 0);
+    }
+
+    #[test]
+    fn test_extension_statistics() {
+        // 创建测试目录
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        
+        // 创建不同扩展名的文件
+        let extensions = ["txt", "log", "mp4", "", "TXT", "Log"]; // 包含空扩展名和大写
+        for (i, &ext) in extensions.iter().enumerate() {
+            let filename = if ext.is_empty() {
+                format!("file{}", i)
+            } else {
+                format!("file{}.{}", i, ext)
+            };
+            let file_path = root.join(filename);
+            let mut file = File::create(&file_path).unwrap();
+            file.write_all(format!("Content {}", i).as_bytes()).unwrap();
+        }
+        
+        // 执行扫描
+        let request = ScanRequest::new(root);
+        let scanner = Scanner::new();
+        let result = scanner.scan_sync(&request).unwrap();
+        
+        // 验证扩展名统计
+        assert_eq!(result.by_extension.len(), 4); // txt, log, mp4, no_extension (空扩展名)
+        // 查找每个扩展名的统计
+        let mut found_txt = false;
+        let mut found_log = false;
+        let mut found_mp4 = false;
+        let mut found_no_ext = false;
+        for stat in &result.by_extension {
+            match stat.extension.as_str() {
+                "txt" => {
+                    assert_eq!(stat.file_count, 2); // txt 和 TXT
+                    found_txt = true;
+                }
+                "log" => {
+                    assert_eq!(stat.file_count, 2); // log 和 Log
+                    found_log = true;
+                }
+                "mp4" => {
+                    assert_eq!(stat.file_count, 1);
+                    found_mp4 = true;
+                }
+                "no_extension" => {
+                    assert_eq!(stat.file_count, 1); // 空扩展名文件
+                    found_no_ext = true;
+                }
+                _ => {}
+            }
+        }
+        assert!(found_txt);
+        assert!(found_log);
+        assert!(found_mp4);
+        assert!(found_no_ext);
+    }
+
+    #[test]
+    fn test_top_n_files() {
+        // 创建测试目录
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        
+        // 创建多个不同大小的文件
+        let sizes = vec![100, 500, 300, 800, 200, 700, 400, 600, 900, 50];
+        for (i, &size) in sizes.iter().enumerate() {
+            let file_path = root.join(format!("file{}.txt", i));
+            let mut file = File::create(&file_path).unwrap();
+            // 写入指定大小的内容
+            let content = vec![b'a'; size];
+            file.write_all(&content).unwrap();
+        }
+        
+        // 测试默认 limit (20) 应返回所有文件（10个），按大小降序
+        let request = ScanRequest::new(root);
+        let scanner = Scanner::new();
+        let result = scanner.scan_sync(&request).unwrap();
+        
+        // 验证 top_files 长度等于文件数（因为文件数少于默认 limit 20）
+        assert_eq!(result.top_files.len(), 10);
+        // 验证顺序是降序
+        for i in 0..result.top_files.len() - 1 {
+            assert!(
+                result.top_files[i].size_bytes >= result.top_files[i + 1].size_bytes,
+                "文件未按大小降序排列: {} < {}",
+                result.top_files[i].size_bytes,
+                result.top_files[i + 1].size_bytes
+            );
+        }
+        // 验证最大文件是 900 字节
+        assert_eq!(result.top_files[0].size_bytes, 900);
+        // 验证最小文件是 50 字节
+        assert_eq!(result.top_files[9].size_bytes, 50);
+        
+        // 测试指定 limit = 5
+        let mut request_limit = ScanRequest::new(root);
+        request_limit.limit = Some(5);
+        let result_limit = scanner.scan_sync(&request_limit).unwrap();
+        assert_eq!(result_limit.top_files.len(), 5);
+        // 验证返回的是最大的5个文件
+        assert_eq!(result_limit.top_files[0].size_bytes, 900);
+        assert_eq!(result_limit.top_files[1].size_bytes, 800);
+        assert_eq!(result_limit.top_files[2].size_bytes, 700);
+        assert_eq!(result_limit.top_files[3].size_bytes, 600);
+        assert_eq!(result_limit.top_files[4].size_bytes, 500);
+        
+        // 测试 limit = 0（应视为无限制？但实际 limit 应该大于0，这里测试默认行为）
+        // 跳过，因为 limit 为 0 时堆容量为 0，可能不存储任何文件。我们假设 limit >= 1
+    }
+
+    #[test]
+    fn test_top_n_files_with_same_size() {
+        // 测试文件大小相同时，按路径排序
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        
+        // 创建三个大小相同的文件，路径不同
+        let paths = ["a.txt", "b.txt", "c.txt"];
+        for &path in &paths {
+            let file_path = root.join(path);
+            let mut file = File::create(&file_path).unwrap();
+            file.write_all(b"same size").unwrap();
+        }
+        
+        let request = ScanRequest::new(root);
+        let scanner = Scanner::new();
+        let result = scanner.scan_sync(&request).unwrap();
+        
+        // 所有文件大小相同，应全部包含（因为 limit 默认20）
+        assert_eq!(result.top_files.len(), 3);
+        // 验证按路径排序（降序？我们的排序是 size 降序，然后 path 降序）
+        // 由于 size 相同，按 path 降序排列，所以 c.txt 应该在 a.txt 之前
+        let filenames: Vec<&str> = result.top_files.iter().map(|e| e.path.file_name().unwrap().to_str().unwrap()).collect();
+        // 注意：排序是 b.path.cmp(&a.path) 降序，所以是反向字母顺序
+        assert_eq!(filenames, vec!["c.txt", "b.txt", "a.txt"]);
+    }
+
+    #[test]
+    fn test_top_n_files_zero_size() {
+        // 测试大小为0的文件应被过滤
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        
+        // 创建一个正常文件和一个大小为0的文件
+        let normal_path = root.join("normal.txt");
+        let mut normal_file = File::create(&normal_path).unwrap();
+        normal_file.write_all(b"content").unwrap();
+        
+        let zero_path = root.join("zero.txt");
+        File::create(&zero_path).unwrap(); // 空文件，大小为0
+        
+        let request = ScanRequest::new(root);
+        let scanner = Scanner::new();
+        let result = scanner.scan_sync(&request).unwrap();
+        
+        // 总文件数应该是2
+        assert_eq!(result.summary.total_files, 2);
+        // 但 top_files 应只包含非零文件（1个）
+        assert_eq!(result.top_files.len(), 1);
+        assert_eq!(result.top_files[0].size_bytes, 7);
+    }
+
+    #[test]
+    fn test_exclude_file_pattern() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+
+        // 创建应被排除的 .log 文件与一个正常文件
+        let log_path = root.join("skip.log");
+        let mut log_file = File::create(&log_path).unwrap();
+        log_file.write_all(b"log content").unwrap();
+
+        let data_path = root.join("data.bin");
+        let mut data_file = File::create(&data_path).unwrap();
+        data_file.write_all(&vec![b'a'; 1024]).unwrap();
+
+        // 设置排除模式：排除所有 .log 文件
+        let mut request = ScanRequest::new(root);
+        request.exclude_patterns = vec!["**/*.log".to_string(), "*.log".to_string()];
+
+        let scanner = Scanner::new();
+        let result = scanner.scan_sync(&request).unwrap();
+
+        // 总文件数仍计数为2，但按 min_size 未过滤；我们只断言 top_files 不包含 .log，扩展统计也不包含 log
+        assert_eq!(result.summary.total_files, 1, "排除文件后计数应为1");
+        // 验证 top_files 中不包含 skip.log
+        assert!(result.top_files.iter().all(|e| e.path.file_name().unwrap() != "skip.log"));
+        // 验证扩展统计不包含 log
+        assert!(result.by_extension.iter().all(|s| s.extension != "log"));
+    }
+
+    #[test]
+    fn test_exclude_directory_pattern() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+
+        // 创建一个子目录及其中的文件
+        let subdir = root.join("sub");
+        fs::create_dir(&subdir).unwrap();
+        let file_in_sub = subdir.join("a.txt");
+        let mut f = File::create(&file_in_sub).unwrap();
+        f.write_all(b"hello").unwrap();
+
+        // 根目录下也创建一个文件
+        let file_root = root.join("b.txt");
+        let mut fr = File::create(&file_root).unwrap();
+        fr.write_all(b"world").unwrap();
+
+        // 排除整个子目录
+        let mut request = ScanRequest::new(root);
+        request.exclude_patterns = vec!["**/sub/**".to_string(), "sub/**".to_string(), "sub".to_string()];
+
+        let scanner = Scanner::new();
+        let result = scanner.scan_sync(&request).unwrap();
+
+        // 应只统计根目录文件
+        assert_eq!(result.summary.total_files, 1);
+        // Top 文件应只包含 b.txt
+        assert_eq!(result.top_files.len(), 1);
+        assert_eq!(result.top_files[0].path.file_name().unwrap(), "b.txt");
+    }
+
+    #[test]
+    fn test_dir_tree_aggregation() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+
+        // 目录结构：
+        // root/a.bin (100)
+        // root/big/b.bin (300)
+        // root/big/nested/c.bin (500)
+        // root/small/d.bin (50)
+        // root/empty/
+        File::create(root.join("a.bin")).unwrap().write_all(&[b'a'; 100]).unwrap();
+        fs::create_dir_all(root.join("big/nested")).unwrap();
+        File::create(root.join("big/b.bin")).unwrap().write_all(&[b'b'; 300]).unwrap();
+        File::create(root.join("big/nested/c.bin")).unwrap().write_all(&[b'c'; 500]).unwrap();
+        fs::create_dir(root.join("small")).unwrap();
+        File::create(root.join("small/d.bin")).unwrap().write_all(&[b'd'; 50]).unwrap();
+        fs::create_dir(root.join("empty")).unwrap();
+
+        let request = ScanRequest::new(root);
+        let scanner = Scanner::new();
+        let result = scanner.scan_sync(&request).unwrap();
+        let tree = &result.tree;
+
+        // 根节点累计值应与摘要一致
+        assert_eq!(tree.path, root);
+        assert_eq!(tree.size_bytes, result.summary.total_size_bytes);
+        assert_eq!(tree.file_count, result.summary.total_files);
+        assert_eq!(tree.dir_count + 1, result.summary.total_dirs);
+        assert_eq!(tree.size_bytes, 950);
+
+        // 子目录按累计大小降序
+        let names: Vec<&str> = tree.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["big", "small", "empty"]);
+
+        let big = &tree.children[0];
+        assert_eq!(big.size_bytes, 800);
+        assert_eq!(big.file_count, 2);
+        assert_eq!(big.dir_count, 1);
+        assert_eq!(big.children[0].size_bytes, 500);
+        assert_eq!(tree.children[2].size_bytes, 0);
+
+        let nested = &big.children[0];
+        assert_eq!(nested.path, root.join("big/nested"));
+        assert_eq!(nested.file_count, 1);
+
+        // 截断深度后累计值保持不变
+        let shallow = tree.truncated(1);
+        assert_eq!(shallow.children.len(), 3);
+        assert!(shallow.children.iter().all(|c| c.children.is_empty()));
+        assert_eq!(shallow.children[0].size_bytes, 800);
+        assert!(tree.truncated(0).children.is_empty());
+
+        // 按路径查找子树，用于下钻
+        assert_eq!(tree.find(&root.join("big/nested")).unwrap().size_bytes, 500);
+        assert_eq!(tree.find(root).unwrap().size_bytes, 950);
+        assert!(tree.find(&root.join("big/missing")).is_none());
+        assert!(tree.find(Path::new("/elsewhere")).is_none());
+    }
+
+    #[test]
+    fn test_scan_with_progress() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for i in 0..3 {
+            let subdir = root.join(format!("dir{}", i));
+            fs::create_dir(&subdir).unwrap();
+            File::create(subdir.join("file.bin")).unwrap().write_all(&[b'x'; 10]).unwrap();
+        }
+
+        let events = Mutex::new(Vec::new());
+        let request = ScanRequest::new(root);
+        let scanner = Scanner::new();
+        let result = scanner
+            .scan_with_progress(&request, |p| events.lock().unwrap().push(p.clone()))
+            .unwrap();
+
+        let events = events.into_inner().unwrap();
+        // 扫描结束时必然上报一次 Completed
+        let last = events.last().unwrap();
+        assert_eq!(last.state, ScanState::Completed);
+        assert_eq!(last.progress, 1.0);
+        assert_eq!(last.scanned_files, result.summary.total_files);
+        assert_eq!(last.scanned_bytes, 30);
+        // 进度单调不回退
+        for pair in events.windows(2) {
+            assert!(pair[0].progress <= pair[1].progress);
+        }
+        assert!(!result.summary.canceled);
+    }
+
+    #[test]
+    fn test_scan_canceled_before_start() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for i in 0..5 {
+            let subdir = root.join(format!("dir{}", i));
+            fs::create_dir(&subdir).unwrap();
+            File::create(subdir.join("file.bin")).unwrap().write_all(&[b'x'; 10]).unwrap();
+        }
+
+        // 在开始前就取消：扫描应立即返回（空的）部分结果，而不是报错
+        let scanner = Scanner::new();
+        scanner.cancel();
+        let states = Mutex::new(Vec::new());
+        let result = scanner
+            .scan_with_progress(&ScanRequest::new(root), |p| states.lock().unwrap().push(p.state))
+            .unwrap();
+        assert!(result.summary.canceled);
+        assert_eq!(result.summary.total_files, 0);
+        assert_eq!(states.into_inner().unwrap().last(), Some(&ScanState::Canceled));
+        assert_eq!(result.tree.file_count, 0);
+    }
+
+    #[test]
+    fn test_size_mode_apparent_vs_allocated() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+
+        // 稀疏文件：逻辑大小 64MB，几乎不占磁盘
+        let sparse = File::create(root.join("disk.img")).unwrap();
+        sparse.set_len(64 * 1024 * 1024).unwrap();
+        // 小文件：逻辑大小 1 字节，至少占一个块
+        File::create(root.join("tiny.txt")).unwrap().write_all(b"x").unwrap();
+
+        let scanner = Scanner::new();
+        let apparent = scanner.scan_sync(&ScanRequest::new(root)).unwrap();
+        let mut request = ScanRequest::new(root);
+        request.size_mode = SizeMode::Allocated;
+        let allocated = scanner.scan_sync(&request).unwrap();
+
+        // 两种口径下都同时报告逻辑大小与占用空间
+        assert_eq!(apparent.summary.total_apparent_bytes, 64 * 1024 * 1024 + 1);
+        assert_eq!(apparent.summary.total_apparent_bytes, allocated.summary.total_apparent_bytes);
+        assert_eq!(apparent.summary.total_allocated_bytes, allocated.summary.total_allocated_bytes);
+        assert_eq!(apparent.summary.total_size_bytes, apparent.summary.total_apparent_bytes);
+        assert_eq!(allocated.summary.total_size_bytes, allocated.summary.total_allocated_bytes);
+        assert!(allocated.summary.total_allocated_bytes < 64 * 1024 * 1024);
+
+        // 逻辑口径下稀疏文件排第一，占用口径下则不然
+        assert_eq!(apparent.top_files[0].path.file_name().unwrap(), "disk.img");
+        let tiny = allocated.top_files.iter().find(|f| f.path.ends_with("tiny.txt")).unwrap();
+        assert_eq!(tiny.apparent_bytes, 1);
+        assert!(tiny.allocated_bytes >= 512);
+        assert_eq!(tiny.size_bytes, tiny.allocated_bytes);
+        assert_eq!(allocated.tree.size_bytes, allocated.summary.total_size_bytes);
+    }
+
+    #[test]
+    fn test_hardlinks_counted_once() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("backup")).unwrap();
+
+        File::create(root.join("data.bin")).unwrap().write_all(&[b'a'; 1000]).unwrap();
+        fs::hard_link(root.join("data.bin"), root.join("backup/data.bin")).unwrap();
+        fs::hard_link(root.join("data.bin"), root.join("backup/data2.bin")).unwrap();
+        File::create(root.join("other.bin")).unwrap().write_all(&[b'b'; 10]).unwrap();
+
+        let scanner = Scanner::new();
+        let result = scanner.scan_sync(&ScanRequest::new(root)).unwrap();
+
+        // 三个链接指向同一个 inode，只计一次
+        assert_eq!(result.summary.total_files, 2);
+        assert_eq!(result.summary.total_size_bytes, 1010);
+        assert_eq!(result.summary.hardlink_shared_bytes, 2000);
+        assert_eq!(result.tree.size_bytes, 1010);
+        let data_links = result.top_files.iter().filter(|f| f.size_bytes == 1000).count();
+        assert_eq!(data_links, 1);
+    }
+
+    #[test]
+    fn test_symlink_policy_and_loops() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("a")).unwrap();
+        File::create(root.join("a/file.bin")).unwrap().write_all(&[b'a'; 100]).unwrap();
+        // a/up -> 根目录（环路），b -> a（同一目录的第二条路径）
+        std::os::unix::fs::symlink(root, root.join("a/up")).unwrap();
+        std::os::unix::fs::symlink(root.join("a"), root.join("b")).unwrap();
+
+        let scanner = Scanner::new();
+
+        // 默认不跟随：两个链接按文件计，不会跳过任何目录
+        let result = scanner.scan_sync(&ScanRequest::new(root)).unwrap();
+        assert_eq!(result.summary.total_dirs, 2);
+        assert_eq!(result.summary.total_files, 3);
+        assert!(result.skipped.is_empty());
+
+        // 跟随：扫描能结束，目录与文件都只计一次，环路被报告
+        let mut request = ScanRequest::new(root);
+        request.follow_symlinks = true;
+        let result = scanner.scan_sync(&request).unwrap();
+        assert_eq!(result.summary.total_dirs, 2);
+        assert_eq!(result.summary.total_files, 1);
+        assert_eq!(result.summary.total_size_bytes, 100);
+        assert_eq!(result.skipped.len(), 2);
+        assert!(result.skipped.iter().all(|s| s.reason == SkipReason::Loop));
+
+        // 单一文件系统模式下同一设备上的目录照常扫描
+        let mut request = ScanRequest::new(root);
+        request.one_file_system = true;
+        let result = scanner.scan_sync(&request).unwrap();
+        assert_eq!(result.summary.total_dirs, 2);
+        assert!(result.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_unreadable_dir_reported() {
+        // root 用户不受目录权限限制，无法构造不可读目录
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        File::create(root.join("visible.bin")).unwrap().write_all(&[b'v'; 400]).unwrap();
+        let locked = root.join("locked");
+        fs::create_dir(&locked).unwrap();
+        File::create(locked.join("hidden.bin")).unwrap().write_all(&[b'h'; 400]).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+        let result = Scanner::new().scan_sync(&ScanRequest::new(root));
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        let result = result.unwrap();
+
+        assert_eq!(result.summary.total_files, 1);
+        assert_eq!(result.summary.error_count, 1);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].path, locked);
+        assert_eq!(result.errors[0].kind, ScanErrorKind::PermissionDenied);
+        // 已读的一个目录共 400 字节，因此估算未扫描的目录也约为 400 字节
+        assert_eq!(result.summary.unscanned_bytes_estimate, 400);
+    }
+
+    #[test]
+    fn test_scan_errors_capped() {
+        let counters = AtomicCounters::new(10);
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        for i in 0..(MAX_SCAN_ERRORS + 5) {
+            counters.add_error(PathBuf::from(format!("/denied/{:03}", i)), &denied, true);
+        }
+        counters.add_error(PathBuf::from("/gone"), &std::io::Error::from(std::io::ErrorKind::NotFound), false);
+
+        // 总数完整计入，但保留的条目不超过上限
+        let summary = counters.to_summary(PathBuf::from("/"), 0.0, false);
+        assert_eq!(summary.error_count, MAX_SCAN_ERRORS as u64 + 6);
+        let errors = counters.errors_to_vec();
+        assert_eq!(errors.len(), MAX_SCAN_ERRORS);
+        assert!(errors.iter().all(|e| e.kind == ScanErrorKind::PermissionDenied));
+    }
+
+    #[test]
+    fn test_incremental_rescan_reuses_unchanged_dirs() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        let cache_path = dir.path().join("scan-cache.bin");
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("b")).unwrap();
+        File::create(root.join("a/x.bin")).unwrap().write_all(&[b'x'; 100]).unwrap();
+        File::create(root.join("b/y.bin")).unwrap().write_all(&[b'y'; 200]).unwrap();
+        // 刚修改过的目录不会写入缓存，先把目录 mtime 调到一小时前
+        let old = SystemTime::now() - Duration::from_secs(3600);
+        for d in [root.clone(), root.join("a"), root.join("b")] {
+            File::open(&d).unwrap().set_modified(old).unwrap();
+        }
+
+        let scanner = Scanner::new();
+        let mut request = ScanRequest::new(&root);
+        request.cache_path = Some(cache_path.clone());
+
+        // 首次扫描：缓存为空，全部从磁盘读取
+        let first = scanner.scan_sync(&request).unwrap();
+        assert_eq!((first.summary.dirs_read, first.summary.dirs_reused), (3, 0));
+        assert!(cache_path.exists());
+
+        // 再次扫描：全部复用，结果一致
+        let second = scanner.scan_sync(&request).unwrap();
+        assert_eq!((second.summary.dirs_read, second.summary.dirs_reused), (0, 3));
+        assert_eq!(second.summary.total_files, 2);
+        assert_eq!(second.summary.total_size_bytes, 300);
+        assert_eq!(second.top_files[0].path, root.join("b/y.bin"));
+        assert_eq!(second.tree.children.len(), 2);
+
+        // 新增文件改变了 b 的 mtime，只有 b 需要重新读取
+        File::create(root.join("b/z.bin")).unwrap().write_all(&[b'z'; 50]).unwrap();
+        let third = scanner.scan_sync(&request).unwrap();
+        assert_eq!((third.summary.dirs_read, third.summary.dirs_reused), (1, 2));
+        assert_eq!(third.summary.total_files, 3);
+        assert_eq!(third.summary.total_size_bytes, 350);
+    }
+}
+
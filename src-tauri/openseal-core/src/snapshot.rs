@@ -0,0 +1,387 @@
+//! Saved scan snapshots and diffs between them.
+//!
+//! A snapshot is one JSON file per scan under `<app data dir>/snapshots`,
+//! holding the full `ScanResult` plus the cumulative size of every directory
+//! in its tree. Next to it sits a small `<id>.info.json` with what the
+//! snapshot list shows, so listing does not parse every tree. Files carry a
+//! format version so older snapshots can be rejected cleanly once the layout
+//! changes.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{OpenSealError, Result};
+use crate::scanner::{DirNode, ScanResult};
+
+/// Current snapshot file format version.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub id: String,
+    pub label: Option<String>,
+    /// Unix timestamp (seconds) the snapshot was saved at
+    pub created_at: u64,
+    /// Cumulative size of every directory in the scanned tree
+    pub dir_sizes: BTreeMap<PathBuf, u64>,
+    pub result: ScanResult,
+}
+
+/// Snapshot metadata, as shown in the snapshot list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub label: Option<String>,
+    pub created_at: u64,
+    pub root_path: PathBuf,
+    pub total_files: u64,
+    pub total_size_bytes: u64,
+}
+
+impl From<&Snapshot> for SnapshotInfo {
+    fn from(snapshot: &Snapshot) -> Self {
+        SnapshotInfo {
+            id: snapshot.id.clone(),
+            label: snapshot.label.clone(),
+            created_at: snapshot.created_at,
+            root_path: snapshot.result.summary.root_path.clone(),
+            total_files: snapshot.result.summary.total_files,
+            total_size_bytes: snapshot.result.summary.total_size_bytes,
+        }
+    }
+}
+
+/// Contents of the `<id>.info.json` sidecar.
+#[derive(Serialize, Deserialize)]
+struct InfoFile {
+    version: u32,
+    #[serde(flatten)]
+    info: SnapshotInfo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Grew,
+    Shrank,
+    Appeared,
+    Disappeared,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffEntry {
+    pub path: PathBuf,
+    pub change: Change,
+    pub old_bytes: u64,
+    pub new_bytes: u64,
+    pub delta_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanDiff {
+    pub from: SnapshotInfo,
+    pub to: SnapshotInfo,
+    pub total_delta_bytes: i64,
+    /// Directories whose cumulative size changed, largest change first
+    pub dirs: Vec<DiffEntry>,
+    /// Files from either snapshot's top-file list whose size changed,
+    /// largest change first
+    pub files: Vec<DiffEntry>,
+}
+
+/// Writes `result` as a new snapshot in `dir` and returns it.
+pub fn save(dir: &Path, result: &ScanResult, label: Option<String>) -> Result<Snapshot> {
+    fs::create_dir_all(dir).map_err(|e| OpenSealError::io(dir, e))?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+
+    let mut dir_sizes = BTreeMap::new();
+    collect_dir_sizes(&result.tree, &mut dir_sizes);
+
+    // Ids are the save time in milliseconds; bump on collision
+    let mut millis = now.as_millis();
+    loop {
+        let snapshot = Snapshot {
+            version: SNAPSHOT_VERSION,
+            id: millis.to_string(),
+            label: label.clone(),
+            created_at: now.as_secs(),
+            dir_sizes: dir_sizes.clone(),
+            result: result.clone(),
+        };
+        let path = snapshot_path(dir, &snapshot.id);
+        match write_new_json(dir, &path, &snapshot) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                millis += 1;
+                continue;
+            }
+            Err(e) => return Err(OpenSealError::io(path, e)),
+        }
+        // Written last, so a sidecar always has its snapshot
+        let info = InfoFile { version: SNAPSHOT_VERSION, info: SnapshotInfo::from(&snapshot) };
+        let info_path = info_path(dir, &snapshot.id);
+        write_new_json(dir, &info_path, &info).map_err(|e| OpenSealError::io(info_path, e))?;
+        return Ok(snapshot);
+    }
+}
+
+/// Writes `value` as JSON to a temp file in `dir` and moves it to `path`, so
+/// a failed write never leaves a truncated file behind. Fails with
+/// `AlreadyExists` rather than replace an existing file.
+fn write_new_json<T: Serialize>(dir: &Path, path: &Path, value: &T) -> io::Result<()> {
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    let mut writer = io::BufWriter::new(tmp.as_file_mut());
+    serde_json::to_writer(&mut writer, value)?;
+    io::Write::flush(&mut writer)?;
+    drop(writer);
+    tmp.persist_noclobber(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Loads the snapshot with the given id from `dir`.
+pub fn load(dir: &Path, id: &str) -> Result<Snapshot> {
+    check_id(id)?;
+    let path = snapshot_path(dir, id);
+    let file = fs::File::open(&path).map_err(|e| OpenSealError::io(&path, e))?;
+    let snapshot: Snapshot = serde_json::from_reader(io::BufReader::new(file))
+        .map_err(|e| OpenSealError::InvalidData { path: path.clone(), message: e.to_string() })?;
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(OpenSealError::InvalidData {
+            path,
+            message: format!("Unsupported snapshot version {} (expected {})", snapshot.version, SNAPSHOT_VERSION),
+        });
+    }
+    Ok(snapshot)
+}
+
+/// Reads a snapshot's metadata from its sidecar, falling back to the full
+/// snapshot when the sidecar is missing or unreadable.
+fn load_info(dir: &Path, id: &str) -> Result<SnapshotInfo> {
+    check_id(id)?;
+    let sidecar = fs::read(info_path(dir, id))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<InfoFile>(&bytes).ok())
+        .filter(|info| info.version == SNAPSHOT_VERSION);
+    match sidecar {
+        Some(info) => Ok(info.info),
+        None => load(dir, id).map(|snapshot| SnapshotInfo::from(&snapshot)),
+    }
+}
+
+/// Lists the readable snapshots in `dir`, newest first.
+pub fn list(dir: &Path) -> Result<Vec<SnapshotInfo>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(OpenSealError::io(dir, e)),
+    };
+    let mut infos: Vec<SnapshotInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let id = name.to_str()?.strip_suffix(".json")?;
+            // Sidecars are read on behalf of their snapshot
+            if id.ends_with(".info") {
+                return None;
+            }
+            load_info(dir, id).ok()
+        })
+        .collect();
+    infos.sort_by_key(|info| std::cmp::Reverse(info.id.parse::<u128>().unwrap_or(0)));
+    Ok(infos)
+}
+
+/// Compares snapshot `from` against the later snapshot `to`. Each list is
+/// ranked by absolute byte delta and cut to `limit` entries. Both snapshots
+/// must be of the same root directory.
+pub fn diff(from: &Snapshot, to: &Snapshot, limit: usize) -> Result<ScanDiff> {
+    let (from_root, to_root) = (&from.result.summary.root_path, &to.result.summary.root_path);
+    if from_root != to_root {
+        return Err(OpenSealError::InvalidInput(format!(
+            "Snapshots are of different directories: {} and {}",
+            from_root.display(),
+            to_root.display()
+        )));
+    }
+    let dir_sizes = |snapshot: &Snapshot| -> HashMap<PathBuf, u64> {
+        snapshot.dir_sizes.iter().map(|(p, s)| (p.clone(), *s)).collect()
+    };
+
+    Ok(ScanDiff {
+        from: SnapshotInfo::from(from),
+        to: SnapshotInfo::from(to),
+        total_delta_bytes: to.result.summary.total_size_bytes as i64 - from.result.summary.total_size_bytes as i64,
+        dirs: diff_sizes(dir_sizes(from), dir_sizes(to), limit),
+        files: diff_files(from, to, limit),
+    })
+}
+
+/// Files are only known through each snapshot's top-file list, so a file
+/// missing from the other list counts as appeared/disappeared only when it is
+/// at least as large as the smallest file that list kept (or the list was
+/// complete). A smaller one may simply have dropped out of the list, and is
+/// left out of the diff.
+fn diff_files(from: &Snapshot, to: &Snapshot, limit: usize) -> Vec<DiffEntry> {
+    let file_sizes = |snapshot: &Snapshot| -> HashMap<PathBuf, u64> {
+        snapshot.result.top_files.iter().map(|f| (f.path.clone(), f.size_bytes)).collect()
+    };
+    // A list holding every scanned file is complete: anything missing is gone
+    let smallest = |snapshot: &Snapshot| {
+        let top_files = &snapshot.result.top_files;
+        if top_files.len() as u64 >= snapshot.result.summary.total_files {
+            return 0;
+        }
+        top_files.iter().map(|f| f.size_bytes).min().unwrap_or(0)
+    };
+
+    let mut old = file_sizes(from);
+    let mut new = file_sizes(to);
+    let (old_floor, new_floor) = (smallest(from), smallest(to));
+    old.retain(|path, size| new.contains_key(path) || *size >= new_floor);
+    new.retain(|path, size| old.contains_key(path) || *size >= old_floor);
+    diff_sizes(old, new, limit)
+}
+
+fn diff_sizes(old: HashMap<PathBuf, u64>, mut new: HashMap<PathBuf, u64>, limit: usize) -> Vec<DiffEntry> {
+    let mut entries = Vec::new();
+    for (path, old_bytes) in old {
+        let new_bytes = new.remove(&path);
+        let change = match new_bytes {
+            None => Change::Disappeared,
+            Some(n) if n > old_bytes => Change::Grew,
+            Some(n) if n < old_bytes => Change::Shrank,
+            Some(_) => continue,
+        };
+        let new_bytes = new_bytes.unwrap_or(0);
+        entries.push(DiffEntry {
+            path,
+            change,
+            old_bytes,
+            new_bytes,
+            delta_bytes: new_bytes as i64 - old_bytes as i64,
+        });
+    }
+    entries.extend(new.into_iter().map(|(path, new_bytes)| DiffEntry {
+        path,
+        change: Change::Appeared,
+        old_bytes: 0,
+        new_bytes,
+        delta_bytes: new_bytes as i64,
+    }));
+
+    entries.sort_by(|a, b| {
+        b.delta_bytes.unsigned_abs().cmp(&a.delta_bytes.unsigned_abs())
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    entries.truncate(limit);
+    entries
+}
+
+fn collect_dir_sizes(node: &DirNode, out: &mut BTreeMap<PathBuf, u64>) {
+    out.insert(node.path.clone(), node.size_bytes);
+    for child in &node.children {
+        collect_dir_sizes(child, out);
+    }
+}
+
+fn check_id(id: &str) -> Result<()> {
+    // Ids are generated numbers; anything else could escape the directory
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(OpenSealError::InvalidInput(format!("Invalid snapshot id: {}", id)));
+    }
+    Ok(())
+}
+
+fn snapshot_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.json", id))
+}
+
+fn info_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.info.json", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{ScanRequest, Scanner};
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_snapshot_roundtrip_and_diff() {
+        let data = tempdir().unwrap();
+        let snapshots = data.path().join("snapshots");
+        let root = data.path().join("scanned");
+        fs::create_dir_all(root.join("logs")).unwrap();
+        fs::create_dir_all(root.join("old")).unwrap();
+        File::create(root.join("logs/app.log")).unwrap().write_all(&[b'l'; 100]).unwrap();
+        File::create(root.join("old/stale.bin")).unwrap().write_all(&[b'o'; 300]).unwrap();
+
+        let scanner = Scanner::new();
+        let first = save(&snapshots, &scanner.scan_sync(&ScanRequest::new(&root)).unwrap(), Some("before".into())).unwrap();
+
+        // The log grows, one directory disappears and another appears
+        File::create(root.join("logs/app.log")).unwrap().write_all(&[b'l'; 1000]).unwrap();
+        fs::remove_dir_all(root.join("old")).unwrap();
+        fs::create_dir_all(root.join("new")).unwrap();
+        File::create(root.join("new/fresh.bin")).unwrap().write_all(&[b'n'; 50]).unwrap();
+        let second = save(&snapshots, &scanner.scan_sync(&ScanRequest::new(&root)).unwrap(), None).unwrap();
+
+        let listed = list(&snapshots).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].id, second.id);
+        assert_eq!(listed[1].label.as_deref(), Some("before"));
+        // Without its sidecar a snapshot is still listed, from the full file
+        fs::remove_file(info_path(&snapshots, &first.id)).unwrap();
+        let listed = list(&snapshots).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[1].total_size_bytes, 400);
+
+        let loaded = load(&snapshots, &first.id).unwrap();
+        assert_eq!(loaded.dir_sizes.get(&root), Some(&400));
+        assert!(load(&snapshots, "../x").is_err());
+
+        let mut elsewhere = loaded.clone();
+        elsewhere.result.summary.root_path = data.path().to_path_buf();
+        assert!(matches!(diff(&elsewhere, &second, 10), Err(OpenSealError::InvalidInput(_))));
+
+        let diff = diff(&loaded, &load(&snapshots, &second.id).unwrap(), 10).unwrap();
+        assert_eq!(diff.total_delta_bytes, 1050 - 400);
+
+        let change_of = |entries: &[DiffEntry], path: PathBuf| {
+            entries.iter().find(|e| e.path == path).map(|e| (e.change, e.delta_bytes))
+        };
+        assert_eq!(change_of(&diff.dirs, root.join("logs")), Some((Change::Grew, 900)));
+        assert_eq!(change_of(&diff.dirs, root.join("old")), Some((Change::Disappeared, -300)));
+        assert_eq!(change_of(&diff.dirs, root.join("new")), Some((Change::Appeared, 50)));
+        assert_eq!(change_of(&diff.files, root.join("old/stale.bin")), Some((Change::Disappeared, -300)));
+        assert_eq!(change_of(&diff.files, root.join("new/fresh.bin")), Some((Change::Appeared, 50)));
+        // Ranked by the size of the change
+        assert_eq!(diff.dirs[0].path, root.join("logs"));
+    }
+
+    #[test]
+    fn test_failed_save_leaves_nothing() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let data = tempdir().unwrap();
+        let snapshots = data.path().join("snapshots");
+        let root = data.path().join("scanned");
+        fs::create_dir_all(&root).unwrap();
+        // JSON cannot hold a path that is not UTF-8, so writing fails part way
+        File::create(root.join(OsStr::from_bytes(b"bad\xff.bin"))).unwrap().write_all(&[0; 10]).unwrap();
+
+        let result = Scanner::new().scan_sync(&ScanRequest::new(&root)).unwrap();
+        assert!(save(&snapshots, &result, None).is_err());
+        assert_eq!(fs::read_dir(&snapshots).unwrap().count(), 0);
+        assert!(list(&snapshots).unwrap().is_empty());
+    }
+}
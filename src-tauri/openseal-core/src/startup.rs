@@ -0,0 +1,54 @@
+//! Launch agents and daemons that start with the system.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct StartupItem {
+    pub name: String,
+    pub path: String,
+    pub kind: String, // "LaunchAgent" or "LaunchDaemon"
+    pub enabled: bool,
+}
+
+/// Lists the user's LaunchAgents (under `home`) and the system-wide
+/// LaunchAgents and LaunchDaemons.
+pub fn startup_items(home: &str) -> Vec<StartupItem> {
+    let mut items = Vec::new();
+    
+    // User LaunchAgents
+    let user_agents = format!("{}/Library/LaunchAgents", home);
+    scan_launch_dir(Path::new(&user_agents), "LaunchAgent", &mut items);
+    
+    // System LaunchAgents
+    scan_launch_dir(Path::new("/Library/LaunchAgents"), "LaunchAgent", &mut items);
+    
+    // System LaunchDaemons
+    scan_launch_dir(Path::new("/Library/LaunchDaemons"), "LaunchDaemon", &mut items);
+    
+    items
+}
+
+fn scan_launch_dir(dir: &Path, kind: &str, items: &mut Vec<StartupItem>) {
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == "plist") {
+                let name = path.file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                
+                // Check if disabled
+                let enabled = !name.contains("disabled");
+                
+                items.push(StartupItem {
+                    name,
+                    path: path.to_string_lossy().to_string(),
+                    kind: kind.to_string(),
+                    enabled,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! Move-to-Trash support.
+//!
+//! macOS uses `~/.Trash` (or `<volume>/.Trashes/<uid>` for other volumes).
+//! Linux follows the freedesktop.org trash spec: the item goes to
+//! `$XDG_DATA_HOME/Trash/files` with a matching `info/<name>.trashinfo`, or to
+//! `<topdir>/.Trash-<uid>` when it lives on another filesystem.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Moves `path` (file, directory or symlink) to the user's trash and returns
+/// the location it was moved to.
+#[cfg(unix)]
+pub fn move_to_trash(path: &Path) -> io::Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    let meta = fs::symlink_metadata(&path)?;
+
+    match trash_into(&path, &home_trash_dir()?) {
+        Err(e) if e.raw_os_error() == Some(libc::EXDEV) => {
+            // The home trash is on another filesystem: use the volume's own trash
+            let topdir = mount_root(&path, &meta)?;
+            trash_into(&path, &volume_trash_dir(&topdir))
+        }
+        result => result,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn move_to_trash(_path: &Path) -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Moving to Trash is not supported on this platform",
+    ))
+}
+
+/// Walks up from `path` to the top directory of the filesystem it lives on.
+#[cfg(unix)]
+fn mount_root(path: &Path, meta: &fs::Metadata) -> io::Result<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut topdir = path.parent().unwrap_or(path).to_path_buf();
+    while let Some(parent) = topdir.parent() {
+        if fs::metadata(parent)?.dev() != meta.dev() {
+            break;
+        }
+        topdir = parent.to_path_buf();
+    }
+    Ok(topdir)
+}
+
+#[cfg(unix)]
+fn home_dir() -> io::Result<PathBuf> {
+    std::env::var_os("HOME")
+        .filter(|h| !h.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "HOME is not set"))
+}
+
+#[cfg(target_os = "macos")]
+fn home_trash_dir() -> io::Result<PathBuf> {
+    Ok(home_dir()?.join(".Trash"))
+}
+
+#[cfg(target_os = "macos")]
+fn volume_trash_dir(topdir: &Path) -> PathBuf {
+    let uid = unsafe { libc::getuid() };
+    topdir.join(".Trashes").join(uid.to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn trash_into(path: &Path, trash_dir: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(trash_dir)?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"))?;
+    for n in 1u32.. {
+        let target = trash_dir.join(numbered_name(name, n));
+        if fs::symlink_metadata(&target).is_ok() {
+            continue;
+        }
+        fs::rename(path, &target)?;
+        return Ok(target);
+    }
+    unreachable!()
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn home_trash_dir() -> io::Result<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .map_or_else(|| home_dir().map(|h| h.join(".local/share")), Ok)?;
+    Ok(data_home.join("Trash"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn volume_trash_dir(topdir: &Path) -> PathBuf {
+    let uid = unsafe { libc::getuid() };
+    topdir.join(format!(".Trash-{}", uid))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn trash_into(path: &Path, trash_dir: &Path) -> io::Result<PathBuf> {
+    use std::io::Write;
+    use std::os::unix::fs::DirBuilderExt;
+
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    for dir in [&files_dir, &info_dir] {
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    }
+
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"))?;
+    for n in 1u32.. {
+        let trashed_name = numbered_name(name, n);
+        let target = files_dir.join(&trashed_name);
+        if fs::symlink_metadata(&target).is_ok() {
+            continue;
+        }
+        // Creating the .trashinfo file exclusively reserves the name
+        let mut info_name = trashed_name.clone();
+        info_name.push(".trashinfo");
+        let info_path = info_dir.join(info_name);
+        let mut info = match fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        };
+        let written = write!(
+            info,
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            percent_encode_path(path),
+            local_timestamp()
+        );
+        if let Err(e) = written.and_then(|_| fs::rename(path, &target)) {
+            let _ = fs::remove_file(&info_path);
+            return Err(e);
+        }
+        return Ok(target);
+    }
+    unreachable!()
+}
+
+/// Returns `name` for the first candidate and `name 2`, `name 3`, ... after
+/// that, keeping the extension at the end (`report 2.pdf`).
+#[cfg(unix)]
+fn numbered_name(name: &std::ffi::OsStr, n: u32) -> std::ffi::OsString {
+    if n == 1 {
+        return name.to_os_string();
+    }
+    let path = Path::new(name);
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => {
+            let mut numbered = stem.to_os_string();
+            numbered.push(format!(" {}.", n));
+            numbered.push(ext);
+            numbered
+        }
+        _ => {
+            let mut numbered = name.to_os_string();
+            numbered.push(format!(" {}", n));
+            numbered
+        }
+    }
+}
+
+/// Percent-encodes a path for the `Path=` key of a `.trashinfo` file.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn percent_encode_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut out = String::new();
+    for &b in path.as_os_str().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// Current local time as `YYYY-MM-DDThh:mm:ss`, as required by `DeletionDate`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn local_timestamp() -> String {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe { libc::localtime_r(&now, &mut tm) };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+#[cfg(all(test, unix, not(target_os = "macos")))]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_trash_into_writes_trashinfo() {
+        let dir = tempdir().unwrap();
+        let trash = dir.path().join("Trash");
+        let victim = dir.path().join("my file.txt");
+
+        File::create(&victim).unwrap();
+        let first = trash_into(&victim, &trash).unwrap();
+        assert_eq!(first, trash.join("files/my file.txt"));
+        assert!(!victim.exists());
+
+        // A second item with the same name must not overwrite the first
+        File::create(&victim).unwrap();
+        let second = trash_into(&victim, &trash).unwrap();
+        assert_eq!(second, trash.join("files/my file 2.txt"));
+
+        let info = fs::read_to_string(trash.join("info/my file.txt.trashinfo")).unwrap();
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(info.contains(&format!("Path={}\n", percent_encode_path(&victim))));
+        assert!(info.contains("my%20file.txt"));
+        assert!(info.contains("DeletionDate="));
+        assert!(trash.join("info/my file 2.txt.trashinfo").exists());
+    }
+}
@@ -0,0 +1,410 @@
+//! Live updates for a finished scan.
+//!
+//! A watcher on the scanned root collects filesystem events, waits for them
+//! to settle, then re-reads only the directories they touched and reports the
+//! per-directory changes. When events may have been lost (the kernel queue or
+//! our own queue overflowed), it falls back to a full rescan.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, TrySendError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::scanner::{dir_tree_from_stats, DirNode, DirStats, ScanLedger, ScanRequest, ScanResult, Scanner};
+
+/// Quiet period that ends a batch of events.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+/// Longest a batch is held back while events keep arriving.
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(3);
+/// Event batches buffered between the notify thread and the update thread.
+const QUEUE_CAPACITY: usize = 4096;
+
+/// Size change of one directory's own files (not including subdirectories).
+#[derive(Debug, Clone, Serialize)]
+pub struct DirDelta {
+    pub path: PathBuf,
+    pub old_bytes: u64,
+    pub new_bytes: u64,
+    pub delta_bytes: i64,
+    pub delta_files: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchDelta {
+    /// Changed directories, largest change first
+    pub changes: Vec<DirDelta>,
+    pub total_files: u64,
+    pub total_size_bytes: u64,
+    /// Set when events were lost and the totals come from a full rescan
+    pub rescanned: bool,
+}
+
+/// Own stats of every directory of a scan, kept up to date, together with
+/// the scan's hardlink and loop bookkeeping so refreshes count like a rescan.
+///
+/// One case still differs from a rescan: when a refreshed directory releases
+/// an inode that also has links in directories that were not refreshed, those
+/// links stay uncounted until their own directory changes.
+pub struct LiveTree {
+    root: PathBuf,
+    ledger: ScanLedger,
+}
+
+impl LiveTree {
+    pub fn new(result: &ScanResult) -> Self {
+        let mut ledger = result.ledger.clone();
+        if ledger.dirs.is_empty() {
+            // A result loaded from disk has no ledger: start from the tree's sizes
+            collect_own_stats(&result.tree, &mut ledger.dirs);
+        }
+        LiveTree { root: result.tree.path.clone(), ledger }
+    }
+
+    pub fn total_files(&self) -> u64 {
+        self.ledger.dirs.values().map(|own| own.files).sum()
+    }
+
+    pub fn total_size_bytes(&self) -> u64 {
+        self.ledger.dirs.values().map(|own| own.size_bytes).sum()
+    }
+
+    /// Writes the current directory tree and totals into `result`. Top files
+    /// and extension stats are not tracked live and stay as of the scan.
+    pub fn apply_to(&self, result: &mut ScanResult) {
+        let dirs = &self.ledger.dirs;
+        result.tree = dir_tree_from_stats(&self.root, dirs);
+        let summary = &mut result.summary;
+        summary.total_files = result.tree.file_count;
+        summary.total_dirs = dirs.len() as u64;
+        summary.total_size_bytes = result.tree.size_bytes;
+        summary.total_apparent_bytes = dirs.values().map(|own| own.apparent_bytes).sum();
+        summary.total_allocated_bytes = dirs.values().map(|own| own.allocated_bytes).sum();
+        summary.hardlink_shared_bytes = dirs.values().map(|own| own.shared_bytes).sum();
+        result.ledger = self.ledger.clone();
+    }
+
+    /// Re-reads the given directories (without descending into known
+    /// subdirectories), scans any subdirectory that appeared and drops any
+    /// that disappeared.
+    pub fn refresh(&mut self, scanner: &Scanner, request: &ScanRequest, dirs: HashSet<PathBuf>) -> Vec<DirDelta> {
+        let mut before = HashMap::new();
+        // A directory we have not seen yet is picked up by its nearest known ancestor
+        let mut dirs: Vec<PathBuf> = dirs
+            .into_iter()
+            .filter(|d| d.starts_with(&self.root))
+            .filter_map(|d| d.ancestors().find(|a| self.ledger.dirs.contains_key(*a)).map(Path::to_path_buf))
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+        // Release the batch's inode claims up front, so a file deleted in one
+        // directory cannot keep an inode reused by a new file in another
+        self.ledger.inodes.retain(|_, owner| dirs.binary_search(owner).is_err());
+        for dir in dirs {
+            // An earlier directory of the batch may have removed this one
+            if self.ledger.dirs.contains_key(&dir) {
+                self.refresh_dir(scanner, request, &dir, &mut before);
+            }
+        }
+        self.deltas(before)
+    }
+
+    /// Replaces the whole tree with a fresh scan result.
+    pub fn replace(&mut self, result: &ScanResult) -> Vec<DirDelta> {
+        let fresh = LiveTree::new(result);
+        let before = std::mem::take(&mut self.ledger.dirs);
+        let paths: HashSet<PathBuf> = before.keys().chain(fresh.ledger.dirs.keys()).cloned().collect();
+        *self = fresh;
+        self.deltas(paths.into_iter().map(|p| {
+            let old = before.get(&p).copied().unwrap_or_default();
+            (p, old)
+        }).collect())
+    }
+
+    fn refresh_dir(
+        &mut self,
+        scanner: &Scanner,
+        request: &ScanRequest,
+        dir: &Path,
+        before: &mut HashMap<PathBuf, DirStats>,
+    ) {
+        // A directory that can no longer be read is treated as gone
+        let shallow = match scanner.scan_dir_shallow(dir, request, &mut self.ledger) {
+            Ok(shallow) => shallow,
+            Err(_) => {
+                self.remove_subtree(dir, before);
+                return;
+            }
+        };
+        self.set(dir, shallow.stats, before);
+
+        let known: Vec<PathBuf> = self.ledger.dirs.keys().filter(|d| d.parent() == Some(dir)).cloned().collect();
+        for gone in known.iter().filter(|d| !shallow.subdirs.contains(d)) {
+            self.remove_subtree(gone, before);
+        }
+        for added in shallow.subdirs.iter().filter(|d| !known.contains(d)) {
+            self.add_subtree(scanner, request, added, before);
+        }
+    }
+
+    /// Reads a directory that appeared, and everything below it.
+    fn add_subtree(&mut self, scanner: &Scanner, request: &ScanRequest, dir: &Path, before: &mut HashMap<PathBuf, DirStats>) {
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            match scanner.scan_dir_shallow(&dir, request, &mut self.ledger) {
+                Ok(shallow) => {
+                    self.set(&dir, shallow.stats, before);
+                    pending.extend(shallow.subdirs);
+                }
+                // Like a full scan, an unreadable directory stays in the tree, empty
+                Err(_) => self.set(&dir, DirStats::default(), before),
+            }
+        }
+    }
+
+    fn set(&mut self, dir: &Path, own: DirStats, before: &mut HashMap<PathBuf, DirStats>) {
+        let old = self.ledger.dirs.insert(dir.to_path_buf(), own).unwrap_or_default();
+        before.entry(dir.to_path_buf()).or_insert(old);
+    }
+
+    fn remove_subtree(&mut self, dir: &Path, before: &mut HashMap<PathBuf, DirStats>) {
+        let gone: Vec<PathBuf> = self.ledger.dirs.keys().filter(|d| d.starts_with(dir)).cloned().collect();
+        for path in gone {
+            let old = self.ledger.dirs.remove(&path).unwrap_or_default();
+            before.entry(path).or_insert(old);
+        }
+        // Inodes counted and directories entered under it are free to be claimed again
+        self.ledger.inodes.retain(|_, owner| !owner.starts_with(dir));
+        self.ledger.visited.retain(|_, path| !path.starts_with(dir));
+    }
+
+    fn deltas(&self, before: HashMap<PathBuf, DirStats>) -> Vec<DirDelta> {
+        let mut deltas: Vec<DirDelta> = before
+            .into_iter()
+            .filter_map(|(path, old)| {
+                let new = self.ledger.dirs.get(&path).copied().unwrap_or_default();
+                if (old.files, old.size_bytes) == (new.files, new.size_bytes) {
+                    return None;
+                }
+                Some(DirDelta {
+                    path,
+                    old_bytes: old.size_bytes,
+                    new_bytes: new.size_bytes,
+                    delta_bytes: new.size_bytes as i64 - old.size_bytes as i64,
+                    delta_files: new.files as i64 - old.files as i64,
+                })
+            })
+            .collect();
+        deltas.sort_by(|a, b| {
+            b.delta_bytes.unsigned_abs().cmp(&a.delta_bytes.unsigned_abs())
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        deltas
+    }
+}
+
+/// Flattens a directory tree into each directory's own files and bytes.
+fn collect_own_stats(node: &DirNode, out: &mut HashMap<PathBuf, DirStats>) {
+    let child_files: u64 = node.children.iter().map(|c| c.file_count).sum();
+    let child_bytes: u64 = node.children.iter().map(|c| c.size_bytes).sum();
+    let own = DirStats {
+        files: node.file_count.saturating_sub(child_files),
+        size_bytes: node.size_bytes.saturating_sub(child_bytes),
+        ..DirStats::default()
+    };
+    out.insert(node.path.clone(), own);
+    for child in &node.children {
+        collect_own_stats(child, out);
+    }
+}
+
+/// A running watcher. Dropping it stops watching.
+pub struct Watch {
+    _watcher: notify::RecommendedWatcher,
+}
+
+/// Watches `request.root_path`, starting from `result`, and calls `on_delta`
+/// on a background thread after each settled batch of changes, with the
+/// updated tree so the caller can bring its copy of the result up to date.
+pub fn watch<F>(request: ScanRequest, result: &ScanResult, on_delta: F) -> Result<Watch>
+where
+    F: Fn(WatchDelta, &LiveTree) + Send + 'static,
+{
+    let (tx, rx) = mpsc::sync_channel::<Vec<PathBuf>>(QUEUE_CAPACITY);
+    let lost = Arc::new(AtomicBool::new(false));
+
+    let lost_events = lost.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let paths = match event {
+            Ok(event) if !event.need_rescan() => event.paths,
+            // The backend dropped events or failed: only a rescan is reliable now
+            _ => {
+                lost_events.store(true, Ordering::SeqCst);
+                Vec::new()
+            }
+        };
+        if let Err(TrySendError::Full(_)) = tx.try_send(paths) {
+            lost_events.store(true, Ordering::SeqCst);
+        }
+    })?;
+    watcher.watch(&request.root_path, RecursiveMode::Recursive)?;
+
+    let mut tree = LiveTree::new(result);
+    std::thread::spawn(move || {
+        let scanner = Scanner::new();
+        // The channel closes when the watcher is dropped
+        while let Ok(paths) = rx.recv() {
+            let mut changed = HashSet::new();
+            add_changed_dirs(&mut changed, paths);
+            let batch_start = Instant::now();
+            loop {
+                let wait = DEBOUNCE.min(MAX_BATCH_DELAY.saturating_sub(batch_start.elapsed()));
+                if wait.is_zero() {
+                    break;
+                }
+                match rx.recv_timeout(wait) {
+                    Ok(paths) => add_changed_dirs(&mut changed, paths),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            let rescanned = lost.swap(false, Ordering::SeqCst);
+            let changes = if rescanned {
+                match scanner.scan_sync(&request) {
+                    Ok(result) => tree.replace(&result),
+                    Err(_) => continue,
+                }
+            } else {
+                tree.refresh(&scanner, &request, changed)
+            };
+            if changes.is_empty() && !rescanned {
+                continue;
+            }
+            let delta = WatchDelta {
+                changes,
+                total_files: tree.total_files(),
+                total_size_bytes: tree.total_size_bytes(),
+                rescanned,
+            };
+            on_delta(delta, &tree);
+        }
+    });
+
+    Ok(Watch { _watcher: watcher })
+}
+
+/// An event on a path changes the listing (or a file size) of its parent.
+fn add_changed_dirs(changed: &mut HashSet<PathBuf>, paths: Vec<PathBuf>) {
+    for path in paths {
+        changed.insert(path.parent().map(Path::to_path_buf).unwrap_or(path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_live_tree_refresh() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        fs::create_dir_all(root.join("logs")).unwrap();
+        fs::create_dir_all(root.join("old/deep")).unwrap();
+        File::create(root.join("logs/app.log")).unwrap().write_all(&[b'l'; 100]).unwrap();
+        File::create(root.join("old/deep/a.bin")).unwrap().write_all(&[b'o'; 300]).unwrap();
+
+        let scanner = Scanner::new();
+        let request = ScanRequest::new(&root);
+        let mut tree = LiveTree::new(&scanner.scan_sync(&request).unwrap());
+        assert_eq!(tree.total_size_bytes(), 400);
+
+        // A file grows, a subtree is removed and a new one is moved in
+        File::create(root.join("logs/app.log")).unwrap().write_all(&[b'l'; 250]).unwrap();
+        fs::remove_dir_all(root.join("old")).unwrap();
+        fs::create_dir_all(root.join("new/inner")).unwrap();
+        File::create(root.join("new/inner/b.bin")).unwrap().write_all(&[b'n'; 40]).unwrap();
+
+        let mut changed = HashSet::new();
+        add_changed_dirs(&mut changed, vec![root.join("logs/app.log"), root.join("old"), root.join("new")]);
+        let deltas = tree.refresh(&scanner, &request, changed);
+
+        assert_eq!(tree.total_files(), 2);
+        assert_eq!(tree.total_size_bytes(), 290);
+        let delta_of = |path: PathBuf| deltas.iter().find(|d| d.path == path).map(|d| d.delta_bytes);
+        assert_eq!(delta_of(root.join("old/deep")), Some(-300));
+        assert_eq!(delta_of(root.join("logs")), Some(150));
+        assert_eq!(delta_of(root.join("new/inner")), Some(40));
+        assert_eq!(deltas[0].path, root.join("old/deep"));
+
+        // Nothing changed since: no deltas
+        let mut changed = HashSet::new();
+        add_changed_dirs(&mut changed, vec![root.join("logs/app.log")]);
+        assert!(tree.refresh(&scanner, &request, changed).is_empty());
+
+        // The scan result follows the live tree
+        let mut result = scanner.scan_sync(&request).unwrap();
+        File::create(root.join("logs/extra.log")).unwrap().write_all(&[b'x'; 10]).unwrap();
+        let mut changed = HashSet::new();
+        add_changed_dirs(&mut changed, vec![root.join("logs/extra.log")]);
+        tree.refresh(&scanner, &request, changed);
+        tree.apply_to(&mut result);
+        assert_eq!(result.summary.total_files, 3);
+        assert_eq!(result.summary.total_size_bytes, 300);
+        assert_eq!(result.tree.find(&root.join("logs")).map(|n| n.size_bytes), Some(260));
+        assert_eq!(result.summary.total_dirs, scanner.scan_sync(&request).unwrap().summary.total_dirs);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_live_totals_match_rescan() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        fs::create_dir_all(root.join("a")).unwrap();
+        File::create(root.join("a/big.bin")).unwrap().write_all(&[b'b'; 500]).unwrap();
+
+        let scanner = Scanner::new();
+        let mut request = ScanRequest::new(&root);
+        request.follow_symlinks = true;
+        let mut result = scanner.scan_sync(&request).unwrap();
+        let mut tree = LiveTree::new(&result);
+
+        // A hardlink of a counted file and a symlink back to the root appear
+        fs::create_dir_all(root.join("b/inner")).unwrap();
+        fs::hard_link(root.join("a/big.bin"), root.join("b/inner/link.bin")).unwrap();
+        File::create(root.join("b/small.bin")).unwrap().write_all(&[b's'; 20]).unwrap();
+        std::os::unix::fs::symlink(&root, root.join("b/up")).unwrap();
+
+        let mut changed = HashSet::new();
+        add_changed_dirs(&mut changed, vec![root.join("b")]);
+        tree.refresh(&scanner, &request, changed);
+        tree.apply_to(&mut result);
+
+        let rescan = scanner.scan_sync(&request).unwrap();
+        assert_eq!(result.summary.total_files, 2);
+        assert_eq!(result.summary.total_files, rescan.summary.total_files);
+        assert_eq!(result.summary.total_dirs, rescan.summary.total_dirs);
+        assert_eq!(result.summary.total_size_bytes, rescan.summary.total_size_bytes);
+        assert_eq!(result.summary.total_apparent_bytes, rescan.summary.total_apparent_bytes);
+        assert_eq!(result.summary.total_allocated_bytes, rescan.summary.total_allocated_bytes);
+        assert_eq!(result.summary.hardlink_shared_bytes, 500);
+        assert_eq!(result.summary.hardlink_shared_bytes, rescan.summary.hardlink_shared_bytes);
+
+        // Once the original is gone, the remaining link is counted in its place
+        fs::remove_dir_all(root.join("a")).unwrap();
+        let mut changed = HashSet::new();
+        add_changed_dirs(&mut changed, vec![root.join("a"), root.join("b/inner/link.bin")]);
+        tree.refresh(&scanner, &request, changed);
+        tree.apply_to(&mut result);
+        assert_eq!(result.summary.total_size_bytes, 520);
+        assert_eq!(result.summary.hardlink_shared_bytes, 0);
+    }
+}
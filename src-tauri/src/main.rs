@@ -3,89 +3,93 @@
     windows_subsystem = "windows"
 )]
 
-mod scanner;
-
-use scanner::{ScanRequest, Scanner};
+use openseal_core::clean::{self, CleanResult, DeleteMode};
+use openseal_core::disk::{self, DiskUsage, Volume};
+use openseal_core::duplicates::{self, DuplicateSet, DuplicateStrategy};
+use openseal_core::junk::{self, JunkCategory};
+use openseal_core::monitor::{ProcessFilter, ProcessMonitor, ProcessSample, ProcessSort};
+use openseal_core::process::{self, IoCounters, OpenFile, ProcessInfo, ProcessNode, ProcessSignal, SignalResult};
+use openseal_core::scanner::{self, ScanProgress, ScanRequest, ScanState, Scanner, SizeMode};
+use openseal_core::startup::{self, StartupItem};
+use openseal_core::units::human_size;
+use openseal_core::{snapshot, watcher, OpenSealError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Manager;
 
 // ── Tauri command types ────────────────────────────────────────────────────
 
-#[derive(Debug, Serialize)]
-struct DiskUsage {
-    total_bytes: u64,
-    used_bytes: u64,
-    free_bytes: u64,
-    total_gb: f64,
-    used_gb: f64,
-    free_gb: f64,
-    usage_percent: f64,
-}
-
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 struct ScanResultResponse {
     summary: scanner::ScanSummary,
     top_files: Vec<FileInfo>,
     by_extension: Vec<scanner::ExtensionStat>,
     stale_files: Vec<FileInfo>,
+    skipped: Vec<scanner::SkippedDir>,
+    errors: Vec<scanner::ScanError>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 struct FileInfo {
     path: String,
     size_bytes: u64,
     size_human: String,
+    apparent_bytes: u64,
+    allocated_bytes: u64,
     extension: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
-struct StartupItem {
-    name: String,
-    path: String,
-    kind: String, // "LaunchAgent" or "LaunchDaemon"
-    enabled: bool,
+#[derive(Debug, Clone, Serialize)]
+struct ScanProgressEvent {
+    scan_id: u64,
+    progress: ScanProgress,
 }
 
-#[derive(Debug, Serialize)]
-struct ProcessInfo {
-    pid: u32,
-    name: String,
-    cpu_percent: f64,
-    memory_mb: f64,
-    command: String,
+#[derive(Debug, Clone, Serialize)]
+struct ScanCompletedEvent {
+    scan_id: u64,
+    result: ScanResultResponse,
 }
 
-// ── O-5: Junk cleaner types ────────────────────────────────────────────────
+#[derive(Debug, Clone, Serialize)]
+struct ScanFailedEvent {
+    scan_id: u64,
+    error: OpenSealError,
+}
 
-#[derive(Debug, Serialize)]
-struct JunkCategory {
-    id: String,
-    name: String,
-    description: String,
-    size_bytes: u64,
-    size_human: String,
-    items: Vec<JunkItem>,
+#[derive(Debug, Clone, Serialize)]
+struct WatchDeltaEvent {
+    scan_id: u64,
+    delta: watcher::WatchDelta,
 }
 
-#[derive(Debug, Serialize)]
-struct JunkItem {
+#[derive(Debug, Clone, Serialize)]
+struct ScanJobStatus {
+    scan_id: u64,
     path: String,
-    size_bytes: u64,
-    size_human: String,
+    progress: ScanProgress,
+    result: Option<ScanResultResponse>,
+    error: Option<OpenSealError>,
 }
 
 #[derive(Debug, Serialize)]
-struct CleanResult {
-    freed_bytes: u64,
-    freed_human: String,
-    deleted_count: u32,
-    errors: Vec<String>,
+struct DirTreeNode {
+    path: String,
+    name: String,
+    size_bytes: u64,
+    size_human: String,
+    file_count: u64,
+    dir_count: u64,
+    children: Vec<DirTreeNode>,
 }
 
 // ── O-7: Duplicate file types ──────────────────────────────────────────────
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct DuplicateGroup {
     hash: String,
     size_bytes: u64,
@@ -103,452 +107,555 @@ struct DuplicateResult {
 
 // ── Helper functions ───────────────────────────────────────────────────────
 
-fn human_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
-    if bytes >= GB {
-        format!("{:.1} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.1} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.1} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
+fn dir_tree_node(node: &scanner::DirNode) -> DirTreeNode {
+    DirTreeNode {
+        path: node.path.to_string_lossy().to_string(),
+        name: node.name.clone(),
+        size_bytes: node.size_bytes,
+        size_human: human_size(node.size_bytes),
+        file_count: node.file_count,
+        dir_count: node.dir_count,
+        children: node.children.iter().map(dir_tree_node).collect(),
     }
 }
 
+/// Runs `f` on a blocking thread, so a slow command does not freeze the UI.
+async fn run_blocking<T, F>(f: F) -> Result<T, OpenSealError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, OpenSealError> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| OpenSealError::Task(e.to_string()))?
+}
+
 // ── O-1: Real disk usage ───────────────────────────────────────────────────
 
 #[tauri::command]
-fn get_disk_usage() -> Result<DiskUsage, String> {
-    use std::process::Command;
-    let output = Command::new("df")
-        .args(["-k", "/"])
-        .output()
-        .map_err(|e| format!("Failed to run df: {}", e))?;
-    
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let lines: Vec<&str> = stdout.lines().collect();
-    if lines.len() < 2 {
-        return Err("Unexpected df output".to_string());
-    }
-    
-    let parts: Vec<&str> = lines[1].split_whitespace().collect();
-    if parts.len() < 4 {
-        return Err("Cannot parse df output".to_string());
-    }
-    
-    let total_kb: u64 = parts[1].parse().unwrap_or(0);
-    let used_kb: u64 = parts[2].parse().unwrap_or(0);
-    let free_kb: u64 = parts[3].parse().unwrap_or(0);
-    
-    let total_bytes = total_kb * 1024;
-    let used_bytes = used_kb * 1024;
-    let free_bytes = free_kb * 1024;
-    
-    Ok(DiskUsage {
-        total_bytes,
-        used_bytes,
-        free_bytes,
-        total_gb: total_bytes as f64 / (1024.0 * 1024.0 * 1024.0),
-        used_gb: used_bytes as f64 / (1024.0 * 1024.0 * 1024.0),
-        free_gb: free_bytes as f64 / (1024.0 * 1024.0 * 1024.0),
-        usage_percent: if total_bytes > 0 { used_bytes as f64 / total_bytes as f64 * 100.0 } else { 0.0 },
-    })
+fn get_disk_usage() -> Result<DiskUsage, OpenSealError> {
+    disk::disk_usage(Path::new("/"))
+}
+
+/// Every mounted filesystem with its capacity, inode usage and whether it is
+/// removable or served over the network.
+#[tauri::command]
+async fn list_volumes() -> Result<Vec<Volume>, OpenSealError> {
+    run_blocking(disk::list_volumes).await
 }
 
 // ── O-2: Disk scan using Surf engine ───────────────────────────────────────
 
-#[tauri::command]
-fn scan_directory(path: String, limit: Option<usize>, min_size_mb: Option<u64>) -> Result<ScanResultResponse, String> {
-    let mut request = ScanRequest::new(&path);
+/// Optional scan settings shared by the scan commands; every field defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ScanOptions {
+    size_mode: SizeMode,
+    follow_symlinks: bool,
+    one_file_system: bool,
+    /// Reuse unchanged directories from the scan cache in the app cache dir
+    incremental: bool,
+}
+
+impl ScanOptions {
+    fn apply(self, request: &mut ScanRequest, app: &tauri::AppHandle) {
+        request.size_mode = self.size_mode;
+        request.follow_symlinks = self.follow_symlinks;
+        request.one_file_system = self.one_file_system;
+        if self.incremental {
+            request.cache_path = app.path_resolver().app_cache_dir().map(|dir| dir.join("scan-cache.bin"));
+        }
+    }
+}
+
+fn directory_scan_request(
+    app: &tauri::AppHandle,
+    path: &str,
+    limit: Option<usize>,
+    min_size_mb: Option<u64>,
+    options: Option<ScanOptions>,
+) -> ScanRequest {
+    let mut request = ScanRequest::new(path);
     request.limit = Some(limit.unwrap_or(20));
+    options.unwrap_or_default().apply(&mut request, app);
     if let Some(mb) = min_size_mb {
         request.min_size = Some(mb * 1024 * 1024);
     }
     request.stale_days = Some(90);
-    
-    let scanner = Scanner::new();
-    let result = scanner.scan_sync(&request).map_err(|e| format!("Scan failed: {}", e))?;
-    
-    let top_files: Vec<FileInfo> = result.top_files.iter().map(|f| FileInfo {
-        path: f.path.to_string_lossy().to_string(),
-        size_bytes: f.size_bytes,
-        size_human: human_size(f.size_bytes),
-        extension: f.extension.clone(),
-    }).collect();
-    
-    let stale_files: Vec<FileInfo> = result.stale_files.iter().map(|f| FileInfo {
+    request
+}
+
+fn file_info(f: &scanner::FileEntry) -> FileInfo {
+    FileInfo {
         path: f.path.to_string_lossy().to_string(),
         size_bytes: f.size_bytes,
         size_human: human_size(f.size_bytes),
+        apparent_bytes: f.apparent_bytes,
+        allocated_bytes: f.allocated_bytes,
         extension: f.extension.clone(),
-    }).collect();
+    }
+}
+
+fn scan_result_response(result: scanner::ScanResult) -> ScanResultResponse {
+    let top_files: Vec<FileInfo> = result.top_files.iter().map(file_info).collect();
+    let stale_files: Vec<FileInfo> = result.stale_files.iter().map(file_info).collect();
     
-    Ok(ScanResultResponse {
+    ScanResultResponse {
         summary: result.summary,
         top_files,
         by_extension: result.by_extension,
         stale_files,
-    })
+        skipped: result.skipped,
+        errors: result.errors,
+    }
 }
 
-// ── O-3: Startup items ─────────────────────────────────────────────────────
-
 #[tauri::command]
-fn get_startup_items() -> Vec<StartupItem> {
-    let mut items = Vec::new();
-    
-    // User LaunchAgents
-    let home = std::env::var("HOME").unwrap_or_default();
-    let user_agents = format!("{}/Library/LaunchAgents", home);
-    scan_launch_dir(&user_agents, "LaunchAgent", &mut items);
-    
-    // System LaunchAgents
-    scan_launch_dir("/Library/LaunchAgents", "LaunchAgent", &mut items);
-    
-    // System LaunchDaemons
-    scan_launch_dir("/Library/LaunchDaemons", "LaunchDaemon", &mut items);
-    
-    items
-}
-
-fn scan_launch_dir(dir: &str, kind: &str, items: &mut Vec<StartupItem>) {
-    if let Ok(entries) = std::fs::read_dir(dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.extension().map_or(false, |e| e == "plist") {
-                let name = path.file_stem()
-                    .map(|s| s.to_string_lossy().to_string())
-                    .unwrap_or_default();
-                
-                // Check if disabled
-                let enabled = !name.contains("disabled");
-                
-                items.push(StartupItem {
-                    name,
-                    path: path.to_string_lossy().to_string(),
-                    kind: kind.to_string(),
-                    enabled,
-                });
-            }
-        }
-    }
+fn scan_directory(
+    app: tauri::AppHandle,
+    path: String,
+    limit: Option<usize>,
+    min_size_mb: Option<u64>,
+    options: Option<ScanOptions>,
+) -> Result<ScanResultResponse, OpenSealError> {
+    let request = directory_scan_request(&app, &path, limit, min_size_mb, options);
+    let scanner = Scanner::new();
+    let result = scanner.scan_sync(&request)?;
+    Ok(scan_result_response(result))
 }
 
-// ── O-4: Process monitor ───────────────────────────────────────────────────
+// ── Background scan jobs ───────────────────────────────────────────────────
 
-#[tauri::command]
-fn get_processes(limit: Option<usize>) -> Result<Vec<ProcessInfo>, String> {
-    use std::process::Command;
-    let output = Command::new("ps")
-        .args(["-eo", "pid,pcpu,rss,comm", "-r"])
-        .output()
-        .map_err(|e| format!("Failed to run ps: {}", e))?;
-    
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let max = limit.unwrap_or(20);
-    let mut processes = Vec::new();
-    
-    for line in stdout.lines().skip(1).take(max) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 4 {
-            let pid: u32 = parts[0].parse().unwrap_or(0);
-            let cpu: f64 = parts[1].parse().unwrap_or(0.0);
-            let rss_kb: f64 = parts[2].parse().unwrap_or(0.0);
-            let command = parts[3..].join(" ");
-            let name = command.split('/').last().unwrap_or(&command).to_string();
-            
-            processes.push(ProcessInfo {
-                pid,
-                name,
-                cpu_percent: cpu,
-                memory_mb: rss_kb / 1024.0,
-                command,
-            });
-        }
-    }
-    
-    Ok(processes)
+struct ScanJob {
+    status: ScanJobStatus,
+    cancel_flag: Arc<AtomicBool>,
+    request: ScanRequest,
+    /// Full result of a finished scan, kept for snapshots and live watching
+    result: Option<scanner::ScanResult>,
 }
 
-// ── O-5: Junk cleaner ──────────────────────────────────────────────────────
+/// Finished scans kept for status, snapshots and watching; older ones are dropped.
+const MAX_FINISHED_JOBS: usize = 8;
 
-fn scan_junk_dir(dir: &str, items: &mut Vec<JunkItem>) -> u64 {
-    let mut total: u64 = 0;
-    let path = Path::new(dir);
-    if !path.exists() {
-        return 0;
-    }
-    if let Ok(entries) = std::fs::read_dir(path) {
-        for entry in entries.flatten() {
-            let p = entry.path();
-            if let Ok(meta) = p.metadata() {
-                if meta.is_file() {
-                    let size = meta.len();
-                    total += size;
-                    items.push(JunkItem {
-                        path: p.to_string_lossy().to_string(),
-                        size_bytes: size,
-                        size_human: human_size(size),
-                    });
-                } else if meta.is_dir() {
-                    total += scan_junk_dir(&p.to_string_lossy(), items);
-                }
-            }
-        }
-    }
-    total
+/// Registry of background scans, held in Tauri managed state.
+#[derive(Default)]
+struct ScanJobs {
+    next_id: AtomicU64,
+    jobs: Mutex<HashMap<u64, ScanJob>>,
 }
 
-#[tauri::command]
-fn scan_junk() -> Vec<JunkCategory> {
-    let home = std::env::var("HOME").unwrap_or_default();
-    let mut categories = Vec::new();
-
-    // 1. System caches (user-level)
-    {
-        let mut items = Vec::new();
-        let dir = format!("{}/Library/Caches", home);
-        let size = scan_junk_dir(&dir, &mut items);
-        categories.push(JunkCategory {
-            id: "system_cache".into(),
-            name: "System & App Cache".into(),
-            description: "Temporary cached data from applications and macOS".into(),
-            size_bytes: size,
-            size_human: human_size(size),
-            items,
-        });
-    }
-
-    // 2. Application logs
-    {
-        let mut items = Vec::new();
-        let dir = format!("{}/Library/Logs", home);
-        let size = scan_junk_dir(&dir, &mut items);
-        categories.push(JunkCategory {
-            id: "app_logs".into(),
-            name: "Application Logs".into(),
-            description: "Log files generated by applications".into(),
-            size_bytes: size,
-            size_human: human_size(size),
-            items,
-        });
+impl ScanJobs {
+    /// The request and full result of a scan that completed without being canceled.
+    fn finished(&self, scan_id: u64) -> Result<(ScanRequest, scanner::ScanResult), OpenSealError> {
+        self.with_finished(scan_id, |request, result| (request.clone(), result.clone()))
     }
 
-    // 3. Trash
-    {
-        let mut items = Vec::new();
-        let dir = format!("{}/.Trash", home);
-        let size = scan_junk_dir(&dir, &mut items);
-        categories.push(JunkCategory {
-            id: "trash".into(),
-            name: "Trash Bin".into(),
-            description: "Files you've deleted but not emptied from Trash".into(),
-            size_bytes: size,
-            size_human: human_size(size),
-            items,
-        });
+    /// Runs `f` on a finished scan's request and result without copying them.
+    fn with_finished<T>(
+        &self,
+        scan_id: u64,
+        f: impl FnOnce(&ScanRequest, &scanner::ScanResult) -> T,
+    ) -> Result<T, OpenSealError> {
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs.get(&scan_id).ok_or(OpenSealError::UnknownScan(scan_id))?;
+        let result = job.result.as_ref().ok_or(OpenSealError::ScanNotFinished(scan_id))?;
+        if result.summary.canceled {
+            return Err(OpenSealError::ScanCanceled(scan_id));
+        }
+        Ok(f(&job.request, result))
     }
 
-    // 4. Temporary files
-    {
-        let mut items = Vec::new();
-        let mut size: u64 = 0;
-        for dir in &["/tmp", "/var/tmp"] {
-            size += scan_junk_dir(dir, &mut items);
+    /// Updates a finished scan's stored result with `f` and refreshes the
+    /// summary reported by its status to match. Returns false if the scan is
+    /// gone.
+    fn update_result(&self, scan_id: u64, f: impl FnOnce(&mut scanner::ScanResult)) -> bool {
+        let mut jobs = self.jobs.lock().unwrap();
+        let Some(job) = jobs.get_mut(&scan_id) else { return false };
+        let Some(result) = job.result.as_mut() else { return false };
+        f(result);
+        if let Some(response) = job.status.result.as_mut() {
+            response.summary = result.summary.clone();
         }
-        categories.push(JunkCategory {
-            id: "temp_files".into(),
-            name: "Temporary Files".into(),
-            description: "System temp files in /tmp and /var/tmp".into(),
-            size_bytes: size,
-            size_human: human_size(size),
-            items,
-        });
+        true
     }
 
-    // 5. Xcode derived data (if exists)
-    {
-        let xcode_dir = format!("{}/Library/Developer/Xcode/DerivedData", home);
-        if Path::new(&xcode_dir).exists() {
-            let mut items = Vec::new();
-            let size = scan_junk_dir(&xcode_dir, &mut items);
-            categories.push(JunkCategory {
-                id: "xcode_derived".into(),
-                name: "Xcode Derived Data".into(),
-                description: "Build artifacts from Xcode projects".into(),
-                size_bytes: size,
-                size_human: human_size(size),
-                items,
-            });
+    fn update_status<F: FnOnce(&mut ScanJobStatus)>(&self, scan_id: u64, f: F) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&scan_id) {
+            f(&mut job.status);
         }
     }
 
-    // 6. npm/yarn cache
-    {
-        let mut items = Vec::new();
-        let mut size: u64 = 0;
-        let npm_cache = format!("{}/.npm/_cacache", home);
-        if Path::new(&npm_cache).exists() {
-            size += scan_junk_dir(&npm_cache, &mut items);
-        }
-        let yarn_cache = format!("{}/Library/Caches/Yarn", home);
-        if Path::new(&yarn_cache).exists() {
-            size += scan_junk_dir(&yarn_cache, &mut items);
-        }
-        if size > 0 {
-            categories.push(JunkCategory {
-                id: "npm_cache".into(),
-                name: "npm/Yarn Cache".into(),
-                description: "Cached packages from npm and Yarn".into(),
-                size_bytes: size,
-                size_human: human_size(size),
-                items,
-            });
+    /// Drops the oldest finished scans beyond `MAX_FINISHED_JOBS`, so their
+    /// trees do not pile up in memory, and stops their watchers.
+    fn evict_finished(&self, watches: &Watches) {
+        let mut jobs = self.jobs.lock().unwrap();
+        let mut finished: Vec<u64> = jobs
+            .iter()
+            .filter(|(_, job)| job.result.is_some() || job.status.error.is_some())
+            .map(|(&scan_id, _)| scan_id)
+            .collect();
+        finished.sort_unstable();
+        let excess = finished.len().saturating_sub(MAX_FINISHED_JOBS);
+        for scan_id in &finished[..excess] {
+            jobs.remove(scan_id);
         }
+        // Still under the jobs lock, so `start_watch` cannot add one back
+        watches.stop(&finished[..excess]);
     }
-
-    // Sort by size descending
-    categories.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
-    categories
 }
 
+/// Starts a scan on a background thread and returns its id immediately.
+///
+/// Progress is emitted as `scan://progress`, followed by exactly one of
+/// `scan://completed` or `scan://failed`, all tagged with the scan id.
+/// A canceled scan still completes, with `summary.canceled` set and the
+/// partial results gathered before it stopped.
 #[tauri::command]
-fn clean_junk(category_ids: Vec<String>) -> CleanResult {
-    let home = std::env::var("HOME").unwrap_or_default();
-    let mut freed: u64 = 0;
-    let mut deleted: u32 = 0;
-    let mut errors = Vec::new();
-
-    let dir_map: HashMap<&str, Vec<String>> = HashMap::from([
-        ("system_cache", vec![format!("{}/Library/Caches", home)]),
-        ("app_logs", vec![format!("{}/Library/Logs", home)]),
-        ("trash", vec![format!("{}/.Trash", home)]),
-        ("temp_files", vec!["/tmp".into(), "/var/tmp".into()]),
-        ("xcode_derived", vec![format!("{}/Library/Developer/Xcode/DerivedData", home)]),
-        ("npm_cache", vec![
-            format!("{}/.npm/_cacache", home),
-            format!("{}/Library/Caches/Yarn", home),
-        ]),
-    ]);
-
-    for cat_id in &category_ids {
-        if let Some(dirs) = dir_map.get(cat_id.as_str()) {
-            for dir in dirs {
-                let path = Path::new(dir);
-                if !path.exists() { continue; }
-                if let Ok(entries) = std::fs::read_dir(path) {
-                    for entry in entries.flatten() {
-                        let p = entry.path();
-                        match if p.is_dir() {
-                            std::fs::remove_dir_all(&p)
-                        } else {
-                            std::fs::remove_file(&p)
-                        } {
-                            Ok(()) => {
-                                if let Ok(meta) = entry.metadata() {
-                                    freed += meta.len();
-                                }
-                                deleted += 1;
-                            },
-                            Err(e) => {
-                                errors.push(format!("{}: {}", p.display(), e));
-                            }
-                        }
-                    }
+fn start_scan(
+    app: tauri::AppHandle,
+    jobs: tauri::State<'_, ScanJobs>,
+    path: String,
+    limit: Option<usize>,
+    min_size_mb: Option<u64>,
+    options: Option<ScanOptions>,
+) -> u64 {
+    let scan_id = jobs.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+    let request = directory_scan_request(&app, &path, limit, min_size_mb, options);
+    let scanner = Scanner::new();
+    
+    jobs.jobs.lock().unwrap().insert(scan_id, ScanJob {
+        status: ScanJobStatus {
+            scan_id,
+            path,
+            progress: ScanProgress::new(ScanState::Queued),
+            result: None,
+            error: None,
+        },
+        cancel_flag: scanner.cancel_flag(),
+        request: request.clone(),
+        result: None,
+    });
+    let _ = app.emit_all("scan://progress", ScanProgressEvent {
+        scan_id,
+        progress: ScanProgress::new(ScanState::Queued),
+    });
+    
+    std::thread::spawn(move || {
+        let jobs = app.state::<ScanJobs>();
+        let result = scanner.scan_with_progress(&request, |progress| {
+            jobs.update_status(scan_id, |status| status.progress = progress.clone());
+            let _ = app.emit_all("scan://progress", ScanProgressEvent {
+                scan_id,
+                progress: progress.clone(),
+            });
+        });
+        match result {
+            Ok(result) => {
+                if let Some(job) = jobs.jobs.lock().unwrap().get_mut(&scan_id) {
+                    job.result = Some(result.clone());
                 }
+                let response = scan_result_response(result);
+                jobs.update_status(scan_id, |status| status.result = Some(response.clone()));
+                let _ = app.emit_all("scan://completed", ScanCompletedEvent {
+                    scan_id,
+                    result: response,
+                });
+            }
+            Err(error) => {
+                jobs.update_status(scan_id, |status| {
+                    status.progress = ScanProgress::new(ScanState::Failed);
+                    status.error = Some(error.clone());
+                });
+                let _ = app.emit_all("scan://progress", ScanProgressEvent {
+                    scan_id,
+                    progress: ScanProgress::new(ScanState::Failed),
+                });
+                let _ = app.emit_all("scan://failed", ScanFailedEvent { scan_id, error });
             }
         }
-    }
+        jobs.evict_finished(&app.state::<Watches>());
+    });
+    
+    scan_id
+}
 
-    CleanResult {
-        freed_bytes: freed,
-        freed_human: human_size(freed),
-        deleted_count: deleted,
-        errors: if errors.len() > 10 { errors[..10].to_vec() } else { errors },
-    }
+#[tauri::command]
+fn cancel_scan(jobs: tauri::State<'_, ScanJobs>, scan_id: u64) -> Result<(), OpenSealError> {
+    let jobs = jobs.jobs.lock().unwrap();
+    let job = jobs.get(&scan_id).ok_or(OpenSealError::UnknownScan(scan_id))?;
+    job.cancel_flag.store(true, Ordering::SeqCst);
+    Ok(())
 }
 
-// ── O-7: Duplicate file detection ──────────────────────────────────────────
+/// Drops a scan and its stored result, canceling it if it is still running
+/// and stopping its watcher.
+#[tauri::command]
+fn forget_scan(
+    jobs: tauri::State<'_, ScanJobs>,
+    watches: tauri::State<'_, Watches>,
+    scan_id: u64,
+) -> Result<(), OpenSealError> {
+    let mut jobs = jobs.jobs.lock().unwrap();
+    let job = jobs.remove(&scan_id).ok_or(OpenSealError::UnknownScan(scan_id))?;
+    job.cancel_flag.store(true, Ordering::SeqCst);
+    // Under the jobs lock, so `start_watch` cannot add it back
+    watches.stop(&[scan_id]);
+    Ok(())
+}
 
 #[tauri::command]
-fn find_duplicates(path: String, min_size_mb: Option<u64>) -> Result<DuplicateResult, String> {
-    use std::collections::HashMap;
-    use std::io::Read;
+fn get_scan_status(jobs: tauri::State<'_, ScanJobs>, scan_id: u64) -> Result<ScanJobStatus, OpenSealError> {
+    let jobs = jobs.jobs.lock().unwrap();
+    jobs.get(&scan_id)
+        .map(|job| job.status.clone())
+        .ok_or(OpenSealError::UnknownScan(scan_id))
+}
 
-    let min_bytes = min_size_mb.unwrap_or(1) * 1024 * 1024;
-    
-    // Phase 1: Group files by size
-    let mut size_map: HashMap<u64, Vec<PathBuf>> = HashMap::new();
-    let root = Path::new(&path);
-    
-    fn walk_dir(dir: &Path, size_map: &mut HashMap<u64, Vec<PathBuf>>, min_bytes: u64) {
-        if let Ok(entries) = std::fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let p = entry.path();
-                if p.is_symlink() { continue; }
-                if p.is_dir() {
-                    // Skip hidden dirs and system dirs
-                    let name = p.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
-                    if name.starts_with('.') || name == "node_modules" || name == "target" || name == ".git" {
-                        continue;
-                    }
-                    walk_dir(&p, size_map, min_bytes);
-                } else if let Ok(meta) = p.metadata() {
-                    if meta.len() >= min_bytes {
-                        size_map.entry(meta.len()).or_default().push(p);
-                    }
-                }
-            }
+/// Returns the directory tree of a finished background scan, `depth` levels
+/// below `path` (the scan root by default). Drilling down reads the stored
+/// tree, so it never scans again.
+#[tauri::command]
+fn get_directory_tree(
+    jobs: tauri::State<'_, ScanJobs>,
+    scan_id: u64,
+    path: Option<String>,
+    depth: Option<usize>,
+) -> Result<DirTreeNode, OpenSealError> {
+    jobs.with_finished(scan_id, |_, result| {
+        let node = match &path {
+            Some(path) => result.tree.find(Path::new(path)).ok_or_else(|| OpenSealError::NotFound(PathBuf::from(path)))?,
+            None => &result.tree,
+        };
+        Ok(dir_tree_node(&node.truncated(depth.unwrap_or(3))))
+    })?
+}
+
+// ── Scan snapshots ─────────────────────────────────────────────────────────
+
+fn snapshot_dir(app: &tauri::AppHandle) -> Result<PathBuf, OpenSealError> {
+    app.path_resolver()
+        .app_data_dir()
+        .map(|dir| dir.join("snapshots"))
+        .ok_or(OpenSealError::AppDirUnavailable)
+}
+
+/// Saves the result of a finished background scan as a snapshot.
+#[tauri::command]
+fn save_snapshot(
+    app: tauri::AppHandle,
+    jobs: tauri::State<'_, ScanJobs>,
+    scan_id: u64,
+    label: Option<String>,
+) -> Result<snapshot::SnapshotInfo, OpenSealError> {
+    let (_, result) = jobs.finished(scan_id)?;
+    let saved = snapshot::save(&snapshot_dir(&app)?, &result, label)?;
+    Ok(snapshot::SnapshotInfo::from(&saved))
+}
+
+#[tauri::command]
+fn list_snapshots(app: tauri::AppHandle) -> Result<Vec<snapshot::SnapshotInfo>, OpenSealError> {
+    snapshot::list(&snapshot_dir(&app)?)
+}
+
+/// Compares snapshot `a` (the earlier one) against snapshot `b`.
+#[tauri::command]
+fn diff_scans(app: tauri::AppHandle, a: String, b: String, limit: Option<usize>) -> Result<snapshot::ScanDiff, OpenSealError> {
+    let dir = snapshot_dir(&app)?;
+    snapshot::diff(&snapshot::load(&dir, &a)?, &snapshot::load(&dir, &b)?, limit.unwrap_or(100))
+}
+
+// ── Live watching ──────────────────────────────────────────────────────────
+
+/// Watchers started on finished scans, keyed by scan id.
+#[derive(Default)]
+struct Watches(Mutex<HashMap<u64, watcher::Watch>>);
+
+impl Watches {
+    fn stop(&self, scan_ids: &[u64]) {
+        let mut watches = self.0.lock().unwrap();
+        for scan_id in scan_ids {
+            watches.remove(scan_id);
         }
     }
-    
-    walk_dir(root, &mut size_map, min_bytes);
-    
-    // Phase 2: For files with same size, compare by partial hash (first 4KB)
-    let mut groups: Vec<DuplicateGroup> = Vec::new();
-    
-    for (size, files) in &size_map {
-        if files.len() < 2 { continue; }
-        
-        let mut hash_map: HashMap<String, Vec<String>> = HashMap::new();
-        for file in files {
-            if let Ok(mut f) = std::fs::File::open(file) {
-                let mut buf = vec![0u8; 4096.min(*size as usize)];
-                if f.read_exact(&mut buf).is_ok() {
-                    // Simple hash: use the bytes as a hex digest
-                    let hash = format!("{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
-                        buf[0], buf[1.min(buf.len()-1)], buf[2.min(buf.len()-1)], buf[3.min(buf.len()-1)],
-                        buf[buf.len()/4], buf[buf.len()/2], buf[buf.len()*3/4], buf[buf.len()-1]);
-                    hash_map.entry(hash).or_default().push(file.to_string_lossy().to_string());
-                }
-            }
+}
+
+/// Watches the root of a finished scan and emits `watch://delta` events as
+/// its directories change, until `stop_watch` is called. The scan's stored
+/// tree and totals follow the changes.
+#[tauri::command]
+async fn start_watch(app: tauri::AppHandle, scan_id: u64) -> Result<(), OpenSealError> {
+    run_blocking(move || {
+        if app.state::<Watches>().0.lock().unwrap().contains_key(&scan_id) {
+            return Ok(());
         }
-        
-        for (hash, paths) in hash_map {
-            if paths.len() >= 2 {
-                groups.push(DuplicateGroup {
-                    hash,
-                    size_bytes: *size,
-                    size_human: human_size(*size),
-                    files: paths,
-                });
+        // Copying the result and watching a large tree take a while, so
+        // neither happens under a lock
+        let (request, result) = app.state::<ScanJobs>().finished(scan_id)?;
+        let handle = app.clone();
+        let watch = watcher::watch(request, &result, move |delta, tree| {
+            if handle.state::<ScanJobs>().update_result(scan_id, |result| tree.apply_to(result)) {
+                let _ = handle.emit_all("watch://delta", WatchDeltaEvent { scan_id, delta });
             }
+        })?;
+
+        // The scan may have been forgotten meanwhile; if another call started
+        // a watcher first, that one is kept and this one dropped
+        let jobs = app.state::<ScanJobs>();
+        let jobs = jobs.jobs.lock().unwrap();
+        if !jobs.contains_key(&scan_id) {
+            return Err(OpenSealError::UnknownScan(scan_id));
         }
+        app.state::<Watches>().0.lock().unwrap().entry(scan_id).or_insert(watch);
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+fn stop_watch(watches: tauri::State<'_, Watches>, scan_id: u64) -> Result<(), OpenSealError> {
+    watches.0.lock().unwrap()
+        .remove(&scan_id)
+        .map(|_| ())
+        .ok_or(OpenSealError::NotWatching(scan_id))
+}
+
+// ── O-3: Startup items ─────────────────────────────────────────────────────
+
+#[tauri::command]
+fn get_startup_items() -> Vec<StartupItem> {
+    let home = std::env::var("HOME").unwrap_or_default();
+    startup::startup_items(&home)
+}
+
+// ── O-4: Process monitor ───────────────────────────────────────────────────
+
+#[tauri::command]
+fn get_processes(limit: Option<usize>) -> Result<Vec<ProcessInfo>, OpenSealError> {
+    process::list_processes(limit.unwrap_or(20))
+}
+
+/// Like `get_processes`, but with CPU and disk I/O measured over the last
+/// few seconds by a background sampler, sorted by `sort` (`cpu`, `memory`
+/// or `io`) and narrowed by `filter`. The first call after a quiet spell
+/// waits for the sampler to take its first two readings.
+#[tauri::command]
+async fn get_top_processes(
+    app: tauri::AppHandle,
+    sort: Option<ProcessSort>,
+    filter: Option<ProcessFilter>,
+    limit: Option<usize>,
+) -> Result<Vec<ProcessSample>, OpenSealError> {
+    run_blocking(move || {
+        app.state::<ProcessMonitor>().top(sort.unwrap_or_default(), &filter.unwrap_or_default(), limit.unwrap_or(20))
+    })
+    .await
+}
+
+/// All processes arranged by parent, with CPU (from the background sampler)
+/// and memory summed per app. `collapse` (default on) folds each app's
+/// helper processes into it.
+#[tauri::command]
+async fn get_process_tree(app: tauri::AppHandle, collapse: Option<bool>) -> Result<Vec<ProcessNode>, OpenSealError> {
+    run_blocking(move || {
+        let processes = app.state::<ProcessMonitor>().processes()?;
+        Ok(process::process_tree(processes, collapse.unwrap_or(true)))
+    })
+    .await
+}
+
+/// Bytes `pid` has read from and written to storage since it started. For
+/// current rates, sort `get_top_processes` by `io`.
+#[tauri::command]
+fn get_process_io(pid: u32) -> Result<IoCounters, OpenSealError> {
+    process::process_io(pid)
+}
+
+/// Open file descriptors of `pid`, with deleted files that still hold disk
+/// space flagged.
+#[tauri::command]
+fn get_open_files(pid: u32) -> Result<Vec<OpenFile>, OpenSealError> {
+    process::open_files(pid)
+}
+
+/// Terminates, kills, suspends or resumes a process. `terminate` waits up to
+/// `grace_ms` (default 3 s) before falling back to SIGKILL, so it runs off
+/// the main thread.
+#[tauri::command]
+async fn signal_process(
+    pid: u32,
+    signal: ProcessSignal,
+    grace_ms: Option<u64>,
+) -> Result<SignalResult, OpenSealError> {
+    let grace = Duration::from_millis(grace_ms.unwrap_or(3000));
+    run_blocking(move || process::signal_process(pid, signal, grace)).await
+}
+
+// ── O-5: Junk cleaner ──────────────────────────────────────────────────────
+
+#[tauri::command]
+fn scan_junk() -> Vec<JunkCategory> {
+    let home = std::env::var("HOME").unwrap_or_default();
+    junk::scan_junk(&home)
+}
+
+/// Empties the given junk categories.
+///
+/// Items are moved to the Trash unless `mode` is `permanent`. With `dry_run`
+/// nothing is touched: the result lists exactly what would be removed and
+/// what is blocked by permissions, resolved the same way as a real run.
+/// Measuring the trees takes a while, so it runs off the main thread.
+#[tauri::command]
+async fn clean_junk(
+    category_ids: Vec<String>,
+    mode: Option<DeleteMode>,
+    dry_run: Option<bool>,
+) -> Result<CleanResult, OpenSealError> {
+    let home = std::env::var("HOME").unwrap_or_default();
+    run_blocking(move || {
+        Ok(clean::clean_categories(&home, &category_ids, mode.unwrap_or_default(), dry_run.unwrap_or(false)))
+    })
+    .await
+}
+
+/// Removes only the selected items returned by `scan_junk`.
+///
+/// Any path that does not lie inside one of the junk category roots is
+/// refused. Items that were already removed since the scan are listed in
+/// `missing` rather than reported as errors.
+#[tauri::command]
+async fn clean_items(
+    paths: Vec<String>,
+    mode: Option<DeleteMode>,
+    dry_run: Option<bool>,
+) -> Result<CleanResult, OpenSealError> {
+    let home = std::env::var("HOME").unwrap_or_default();
+    run_blocking(move || Ok(clean::clean_items(&home, paths, mode.unwrap_or_default(), dry_run.unwrap_or(false)))).await
+}
+
+// ── O-7: Duplicate file detection ──────────────────────────────────────────
+
+/// Hashes candidate files, so it runs off the main thread.
+#[tauri::command]
+async fn find_duplicates(path: String, min_size_mb: Option<u64>) -> Result<DuplicateResult, OpenSealError> {
+    if !Path::new(&path).is_dir() {
+        return Err(OpenSealError::NotFound(PathBuf::from(path)));
     }
-    
-    groups.sort_by(|a, b| {
-        let a_waste = a.size_bytes * (a.files.len() as u64 - 1);
-        let b_waste = b.size_bytes * (b.files.len() as u64 - 1);
-        b_waste.cmp(&a_waste)
-    });
+    let min_bytes = min_size_mb.unwrap_or(1) * 1024 * 1024;
+    let mut sets = run_blocking(move || Ok(duplicates::find_duplicates(Path::new(&path), min_bytes))).await?;
     
     // Limit to top 50 groups
-    groups.truncate(50);
+    sets.truncate(50);
     
-    let total_wasted: u64 = groups.iter().map(|g| g.size_bytes * (g.files.len() as u64 - 1)).sum();
+    let total_wasted: u64 = sets.iter().map(|set| set.wasted_bytes()).sum();
+    let groups: Vec<DuplicateGroup> = sets
+        .into_iter()
+        .map(|set| DuplicateGroup {
+            hash: set.hash,
+            size_bytes: set.size_bytes,
+            size_human: human_size(set.size_bytes),
+            files: set.files.iter().map(|f| f.to_string_lossy().to_string()).collect(),
+        })
+        .collect();
     let total_groups = groups.len();
     
     Ok(DuplicateResult {
@@ -559,18 +666,53 @@ fn find_duplicates(path: String, min_size_mb: Option<u64>) -> Result<DuplicateRe
     })
 }
 
+/// Removes every copy in `group` except `keep`, using `strategy`.
+///
+/// Each file is re-hashed first; files whose contents changed since the scan
+/// are skipped and reported in `errors`. Runs off the main thread.
+#[tauri::command]
+async fn resolve_duplicates(group: DuplicateGroup, keep: String, strategy: DuplicateStrategy) -> Result<CleanResult, OpenSealError> {
+    let set = DuplicateSet {
+        hash: group.hash,
+        size_bytes: group.size_bytes,
+        files: group.files.into_iter().map(PathBuf::from).collect(),
+    };
+    run_blocking(move || duplicates::resolve(&set, Path::new(&keep), strategy)).await
+}
+
 // ── App entry point ────────────────────────────────────────────────────────
 
 fn main() {
     tauri::Builder::default()
+        .manage(ScanJobs::default())
+        .manage(Watches::default())
+        .manage(ProcessMonitor::default())
         .invoke_handler(tauri::generate_handler![
             get_disk_usage,
+            list_volumes,
             scan_directory,
+            start_scan,
+            cancel_scan,
+            forget_scan,
+            get_scan_status,
+            get_directory_tree,
             get_startup_items,
             get_processes,
+            get_top_processes,
+            get_process_tree,
+            get_process_io,
+            get_open_files,
+            signal_process,
             scan_junk,
             clean_junk,
+            clean_items,
             find_duplicates,
+            resolve_duplicates,
+            save_snapshot,
+            list_snapshots,
+            diff_scans,
+            start_watch,
+            stop_watch,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");